@@ -0,0 +1,103 @@
+use crate::periodic::wrap_coordinate;
+
+/// Axis aligned box in `D` dimensions, made of the points with
+/// `min[d] <= p[d] <= max[d]` along every axis `d`. It gives the domain of the
+/// cell lists, `Aabb<D>` for `CellGrid<D>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb<const D: usize> {
+    pub min: [f64; D],
+    pub max: [f64; D],
+}
+
+impl<const D: usize> Aabb<D> {
+    pub fn new(min: [f64; D], max: [f64; D]) -> Aabb<D> {
+        Aabb { min, max }
+    }
+
+    /// The box `-max..max` along every axis.
+    pub fn from_maximum_coordinate(max: f64) -> Aabb<D> {
+        Aabb {
+            min: [-max; D],
+            max: [max; D],
+        }
+    }
+
+    /// Smallest box holding all the `points`, skipping the ones with a non
+    /// finite coordinate. `None` if no point is left.
+    pub fn from_points<I: IntoIterator<Item = [f64; D]>>(points: I) -> Option<Aabb<D>> {
+        points
+            .into_iter()
+            .filter(|p| p.iter().all(|v| v.is_finite()))
+            .fold(None, |aabb: Option<Aabb<D>>, p| match aabb {
+                None => Some(Aabb::new(p, p)),
+                Some(aabb) => Some(aabb.union(&Aabb::new(p, p))),
+            })
+    }
+
+    /// Length of the box along every axis.
+    pub fn lengths(&self) -> [f64; D] {
+        let mut lengths = [0.; D];
+        for (d, length) in lengths.iter_mut().enumerate() {
+            *length = self.max[d] - self.min[d];
+        }
+        lengths
+    }
+
+    pub fn contains(&self, point: [f64; D]) -> bool {
+        (0..D).all(|d| point[d] >= self.min[d] && point[d] <= self.max[d])
+    }
+
+    /// Smallest box holding both boxes.
+    pub fn union(&self, other: &Aabb<D>) -> Aabb<D> {
+        let mut union = *self;
+        for d in 0..D {
+            union.min[d] = self.min[d].min(other.min[d]);
+            union.max[d] = self.max[d].max(other.max[d]);
+        }
+        union
+    }
+
+    /// Overlap of both boxes, `None` if they do not overlap.
+    pub fn intersection(&self, other: &Aabb<D>) -> Option<Aabb<D>> {
+        let mut intersection = *self;
+        for d in 0..D {
+            intersection.min[d] = self.min[d].max(other.min[d]);
+            intersection.max[d] = self.max[d].min(other.max[d]);
+            if intersection.min[d] > intersection.max[d] {
+                return None;
+            }
+        }
+        Some(intersection)
+    }
+
+    /// The box grown by `padding` on every face, or shrunk for a negative
+    /// `padding`.
+    pub fn padded(&self, padding: f64) -> Aabb<D> {
+        let mut padded = *self;
+        for d in 0..D {
+            padded.min[d] -= padding;
+            padded.max[d] += padding;
+        }
+        padded
+    }
+
+    /// Map `point` back into the box along the `periodic` axes, leaving the
+    /// other coordinates untouched.
+    pub fn wrap(&self, point: [f64; D], periodic: [bool; D]) -> [f64; D] {
+        let mut wrapped = point;
+        for d in 0..D {
+            wrapped[d] = wrap_coordinate(point[d], self.min[d], self.max[d], periodic[d]);
+        }
+        wrapped
+    }
+
+    /// Squared distance from `point` to the box, zero if the point is inside.
+    pub fn distance_squared(&self, point: [f64; D]) -> f64 {
+        (0..D)
+            .map(|d| {
+                let outside = (self.min[d] - point[d]).max(point[d] - self.max[d]).max(0.);
+                outside * outside
+            })
+            .sum()
+    }
+}
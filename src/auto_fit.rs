@@ -0,0 +1,65 @@
+use crate::NnpsError;
+
+/// Settings of the auto fit mode of `CellGrid`. With auto fit on,
+/// every registration computes the bounding box of the particles and grows
+/// the domain along the non periodic axes until it covers all of them, so a
+/// drifting simulation never loses particles. The domain never shrinks, and
+/// `head` is only reallocated when the domain actually grows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoFit {
+    /// Margin kept between the particles and a face of the domain when the
+    /// domain grows past that face.
+    pub padding: f64,
+    /// Extra room added on a face every time the domain grows past it, as a
+    /// fraction of the domain length. It gives some hysteresis, so a cloud
+    /// drifting slowly does not trigger a reallocation at every time step.
+    pub growth: f64,
+}
+
+impl AutoFit {
+    pub fn new(padding: f64, growth: f64) -> AutoFit {
+        if padding.is_nan() || padding < 0. || growth.is_nan() || growth < 0. {
+            panic!("the padding and the growth of the auto fit can not be negative");
+        }
+        AutoFit { padding, growth }
+    }
+
+    /// Limits and number of cells of an axis covering `min..max` and the
+    /// particle range `lo..=hi`, or `None` if `min..max` already covers the
+    /// particles. The new length is a whole number of cells. Fails when the
+    /// cells can not be counted, for particles blown far away.
+    pub(crate) fn fit_axis(
+        &self,
+        min: f64,
+        max: f64,
+        lo: f64,
+        hi: f64,
+        cell_size: f64,
+    ) -> Result<Option<(f64, f64, usize)>, NnpsError> {
+        if lo >= min && hi < max {
+            return Ok(None);
+        }
+        let extra = self.growth * (hi.max(max) - lo.min(min));
+        let new_min = if lo < min {
+            lo - self.padding - extra
+        } else {
+            min
+        };
+        let new_max = if hi >= max {
+            hi + self.padding + extra
+        } else {
+            max
+        };
+        // one more cell than the truncated count, so that the particles on
+        // the upper face still fall in the last cell
+        let length = (new_max - new_min) / cell_size;
+        if !length.is_finite() || length >= usize::MAX as f64 {
+            return Err(NnpsError::CellCountOverflow);
+        }
+        let no_cells = (length as usize)
+            .checked_add(1)
+            .ok_or(NnpsError::CellCountOverflow)?;
+        let new_max = new_min + no_cells as f64 * cell_size;
+        Ok(Some((new_min, new_max, no_cells)))
+    }
+}
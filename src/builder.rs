@@ -0,0 +1,199 @@
+use crate::aabb::Aabb;
+use crate::auto_fit::AutoFit;
+use crate::cell_count::CellCountPolicy;
+use crate::cell_grid::CellGrid;
+use crate::cell_ordering::CellOrdering;
+use crate::nbs1d::NBS1D;
+use crate::nbs2d::NBS2D;
+use crate::nbs3d::NBS3D;
+use crate::particle_index::ParticleIndex;
+use crate::real::Real;
+use crate::NnpsError;
+
+/// Named parameter builder for the cell lists, checking every parameter
+/// in one place when the grid is built.
+///
+/// ```
+/// use neighbours::builder::NbsBuilder;
+///
+/// let nbs2d = NbsBuilder::new()
+///     .x_limits(0., 3.)
+///     .y_limits(0., 2.)
+///     .cell_size(1.)
+///     .periodic_x(true)
+///     .no_of_particles(100)
+///     .build_2d()
+///     .unwrap();
+/// assert_eq!(6, nbs2d.total_no_cells);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NbsBuilder {
+    limits: [Option<(f64, f64)>; 3],
+    cell_sizes: Option<[f64; 3]>,
+    periodic: [bool; 3],
+    no_of_particles: usize,
+    capacity: usize,
+    auto_fit: Option<AutoFit>,
+    strict: bool,
+    cell_count_policy: CellCountPolicy,
+    cell_ordering: CellOrdering,
+}
+
+impl NbsBuilder {
+    pub fn new() -> NbsBuilder {
+        NbsBuilder::default()
+    }
+
+    pub fn x_limits(mut self, min: f64, max: f64) -> NbsBuilder {
+        self.limits[0] = Some((min, max));
+        self
+    }
+
+    pub fn y_limits(mut self, min: f64, max: f64) -> NbsBuilder {
+        self.limits[1] = Some((min, max));
+        self
+    }
+
+    /// Limits along `z`, only used in 3D.
+    pub fn z_limits(mut self, min: f64, max: f64) -> NbsBuilder {
+        self.limits[2] = Some((min, max));
+        self
+    }
+
+    /// Set the limits of the first `D` axes from `domain`.
+    pub fn domain<const D: usize>(mut self, domain: Aabb<D>) -> NbsBuilder {
+        if D > 3 {
+            panic!("a domain has at most 3 axes");
+        }
+        for d in 0..D {
+            self.limits[d] = Some((domain.min[d], domain.max[d]));
+        }
+        self
+    }
+
+    /// Set the limits of every axis to `-max..max`, like
+    /// `from_maximum_coordinate`.
+    pub fn max_coordinate(mut self, max: f64) -> NbsBuilder {
+        self.limits = [Some((-max, max)); 3];
+        self
+    }
+
+    pub fn cell_size(mut self, cell_size: f64) -> NbsBuilder {
+        self.cell_sizes = Some([cell_size; 3]);
+        self
+    }
+
+    /// Separate cell sizes along `x`, `y` and `z`, the sizes past the
+    /// dimension of the grid are ignored.
+    pub fn cell_sizes(mut self, dx: f64, dy: f64, dz: f64) -> NbsBuilder {
+        self.cell_sizes = Some([dx, dy, dz]);
+        self
+    }
+
+    pub fn periodic_x(mut self, periodic: bool) -> NbsBuilder {
+        self.periodic[0] = periodic;
+        self
+    }
+
+    pub fn periodic_y(mut self, periodic: bool) -> NbsBuilder {
+        self.periodic[1] = periodic;
+        self
+    }
+
+    pub fn periodic_z(mut self, periodic: bool) -> NbsBuilder {
+        self.periodic[2] = periodic;
+        self
+    }
+
+    /// Number of particles `next` is sized for, like `initialize_next`.
+    pub fn no_of_particles(mut self, no_of_particles: usize) -> NbsBuilder {
+        self.no_of_particles = no_of_particles;
+        self
+    }
+
+    /// Number of particles `next` has room for without reallocating, for
+    /// simulations where particles are added as they go.
+    pub fn capacity(mut self, capacity: usize) -> NbsBuilder {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn auto_fit(mut self, auto_fit: Option<AutoFit>) -> NbsBuilder {
+        self.auto_fit = auto_fit;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> NbsBuilder {
+        self.strict = strict;
+        self
+    }
+
+    pub fn cell_count_policy(mut self, policy: CellCountPolicy) -> NbsBuilder {
+        self.cell_count_policy = policy;
+        self
+    }
+
+    pub fn cell_ordering(mut self, ordering: CellOrdering) -> NbsBuilder {
+        self.cell_ordering = ordering;
+        self
+    }
+
+    /// Build a grid over the first `D` axes, `D` being 1, 2 or 3, for
+    /// particle coordinates of type `T` and indices of type `I`.
+    pub fn build<const D: usize, T: Real, I: ParticleIndex>(
+        &self,
+    ) -> Result<CellGrid<D, T, I>, NnpsError> {
+        let mut domain = Aabb::new([0.; D], [0.; D]);
+        for d in 0..D {
+            (domain.min[d], domain.max[d]) = self.required_limits(d)?;
+        }
+        let cell_sizes = self.required_cell_sizes()?;
+        let cell_sizes = std::array::from_fn(|d| cell_sizes[d]);
+        // the cells are counted under the policy straight away, so that a
+        // rounded up count is checked like the others
+        let mut grid =
+            CellGrid::try_from_aabb_with_policy(domain, cell_sizes, self.cell_count_policy)?;
+        grid.try_set_periodic_axes(std::array::from_fn(|d| self.periodic[d]))?;
+        grid.try_set_cell_ordering(self.cell_ordering)?;
+
+        grid.next = self.new_next()?;
+        grid.auto_fit = self.auto_fit;
+        grid.strict = self.strict;
+        Ok(grid)
+    }
+
+    pub fn build_1d(&self) -> Result<NBS1D, NnpsError> {
+        self.build()
+    }
+
+    pub fn build_2d(&self) -> Result<NBS2D, NnpsError> {
+        self.build()
+    }
+
+    pub fn build_3d(&self) -> Result<NBS3D, NnpsError> {
+        self.build()
+    }
+
+    fn required_limits(&self, axis: usize) -> Result<(f64, f64), NnpsError> {
+        self.limits[axis].ok_or(NnpsError::MissingLimits { axis })
+    }
+
+    fn required_cell_sizes(&self) -> Result<[f64; 3], NnpsError> {
+        self.cell_sizes.ok_or(NnpsError::MissingCellSize)
+    }
+
+    fn new_next<I: ParticleIndex>(&self) -> Result<Vec<I>, NnpsError> {
+        let capacity = self.capacity.max(self.no_of_particles);
+        if capacity > I::MAX_PARTICLES {
+            let max = I::MAX_PARTICLES;
+            let no_of_particles = capacity;
+            return Err(NnpsError::TooManyParticles {
+                no_of_particles,
+                max,
+            });
+        }
+        let mut next = Vec::with_capacity(capacity);
+        next.resize(self.no_of_particles, I::NONE);
+        Ok(next)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::NnpsError;
+
+/// How the cell lists size their grid when the domain length is not a whole
+/// number of cells. Either way the grid covers the whole domain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CellCountPolicy {
+    /// Truncate the number of cells, the last cell of every axis stretching
+    /// up to the upper limit of the domain. Fewest cells, the last ones being
+    /// up to twice as long.
+    #[default]
+    ClampEdge,
+    /// Round the number of cells up, the last cell of every axis reaching
+    /// past the upper limit of the domain. Every cell keeps its size. Periodic
+    /// axes always clamp the edge, their cells have to tile the period.
+    RoundUp,
+}
+
+/// Check the limits of one axis against the cell size and return the number
+/// of cells along it under `policy`.
+pub(crate) fn axis_no_cells(
+    axis: usize,
+    min: f64,
+    max: f64,
+    cell_size: f64,
+    policy: CellCountPolicy,
+) -> Result<usize, NnpsError> {
+    if !(min.is_finite() && max.is_finite() && cell_size.is_finite()) {
+        return Err(NnpsError::NonFinite);
+    }
+    if min >= max {
+        return Err(NnpsError::InvertedBounds { axis, min, max });
+    }
+    if cell_size <= 0. || cell_size > max - min {
+        return Err(NnpsError::InvalidCellSize { cell_size, axis });
+    }
+    let no_cells = match policy {
+        CellCountPolicy::ClampEdge => ((max - min) / cell_size).floor(),
+        CellCountPolicy::RoundUp => ((max - min) / cell_size).ceil(),
+    };
+    if no_cells >= usize::MAX as f64 {
+        return Err(NnpsError::CellCountOverflow);
+    }
+    Ok(no_cells as usize)
+}
+
+/// Total number of cells of a grid, checking that `head` can be allocated.
+pub(crate) fn total_no_cells(no_cells: &[usize]) -> Result<usize, NnpsError> {
+    let max_cells = isize::MAX as usize / std::mem::size_of::<usize>();
+    no_cells
+        .iter()
+        .try_fold(1usize, |total, &n| total.checked_mul(n))
+        .filter(|&total| total <= max_cells)
+        .ok_or(NnpsError::CellCountOverflow)
+}
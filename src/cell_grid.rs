@@ -0,0 +1,832 @@
+use crate::aabb::Aabb;
+use crate::auto_fit::AutoFit;
+use crate::cell_count::{axis_no_cells, total_no_cells, CellCountPolicy};
+use crate::cell_ordering::{no_of_indices, CellOrdering};
+use crate::error::NnpsError;
+use crate::particle_index::ParticleIndex;
+use crate::periodic::{cell_coordinate, wrap_cell};
+use crate::real::Real;
+use crate::registration::{RegistrationError, RegistrationReport};
+use crate::NNPS;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::marker::PhantomData;
+
+/// Offsets along one axis visited by the stencil: the home cell first, then
+/// the lower and the upper neighbours.
+const OFFSETS: [isize; 3] = [0, -1, 1];
+
+/// Cell offsets visited by the 3D stencil, in the traversal order `NBS3D`
+/// has always used: the plane of the home cell, the plane above and the
+/// plane below.
+const STENCIL_3D: [[isize; 3]; 27] = [
+    // the plane of the home cell
+    [0, 0, 0],
+    [-1, 0, 0],
+    [1, 0, 0],
+    [0, -1, 0],
+    [-1, -1, 0],
+    [1, -1, 0],
+    [0, 1, 0],
+    [-1, 1, 0],
+    [1, 1, 0],
+    // the plane above
+    [0, 0, 1],
+    [-1, 0, 1],
+    [1, 0, 1],
+    [0, -1, 1],
+    [1, -1, 1],
+    [-1, -1, 1],
+    [0, 1, 1],
+    [-1, 1, 1],
+    [1, 1, 1],
+    // the plane below
+    [0, 0, -1],
+    [1, 0, -1],
+    [-1, 0, -1],
+    [0, 1, -1],
+    [-1, 1, -1],
+    [1, 1, -1],
+    [0, -1, -1],
+    [1, -1, -1],
+    [-1, -1, -1],
+];
+
+/// Number of cells in the stencil around a home cell, `3^D`.
+pub(crate) const fn stencil_len(dim: usize) -> usize {
+    3usize.pow(dim as u32)
+}
+
+/// Offset of the `k`th cell of the stencil. The first axis varies fastest,
+/// so in 2D the stencil visits the row of the home cell first, then the row
+/// below and the row above. The 3D stencil follows `STENCIL_3D`.
+pub(crate) fn stencil_offset<const D: usize>(k: usize) -> [isize; D] {
+    if D == 3 {
+        return std::array::from_fn(|d| STENCIL_3D[k][d]);
+    }
+    let mut offset = [0; D];
+    let mut k = k;
+    for o in offset.iter_mut() {
+        *o = OFFSETS[k % 3];
+        k /= 3;
+    }
+    offset
+}
+
+/// `true` for the offsets of the half stencil, the ones whose last non zero
+/// component is positive. Of the two cells of a neighbouring pair, exactly
+/// one sees the other through its half stencil, so following the half
+/// stencil visits every pair of particles once.
+fn in_half_stencil<const D: usize>(offset: &[isize; D]) -> bool {
+    offset.iter().rev().find(|&&o| o != 0) == Some(&1)
+}
+
+/// The first `D` of the coordinates `x`, `y` and `z`.
+pub(crate) fn first_coordinates<T: Real, const D: usize>(x: T, y: T, z: T) -> [f64; D] {
+    let all = [x, y, z];
+    std::array::from_fn(|d| all[d].to_f64())
+}
+
+/// Position of particle `i`, taken from the first `D` coordinate slices.
+pub(crate) fn particle_position<T: Real, const D: usize>(coords: &[&[T]; 3], i: usize) -> [f64; D] {
+    std::array::from_fn(|d| coords[d][i].to_f64())
+}
+
+/// Report of the particles `lost` by a registration, sorted into the ones
+/// out of the domain and the ones with a non finite position.
+pub(crate) fn lost_particles_report<T: Real, const D: usize>(
+    coords: &[&[T]; 3],
+    lost: impl IntoIterator<Item = usize>,
+) -> RegistrationReport {
+    let mut report = RegistrationReport::default();
+    for i in lost {
+        let point = particle_position::<T, D>(coords, i);
+        if point.iter().all(|v| v.is_finite()) {
+            report.out_of_domain.push(i);
+        } else {
+            report.non_finite.push(i);
+        }
+    }
+    report
+}
+
+/// Check that the particles can all be indexed with `I`.
+pub(crate) fn try_check_no_of_particles<I: ParticleIndex>(
+    no_of_particles: usize,
+) -> Result<(), NnpsError> {
+    if no_of_particles > I::MAX_PARTICLES {
+        return Err(NnpsError::TooManyParticles {
+            no_of_particles,
+            max: I::MAX_PARTICLES,
+        });
+    }
+    Ok(())
+}
+
+/// An empty `head` with `no_of_indices` lists, or an error if it can not be
+/// allocated.
+fn empty_head<I: ParticleIndex>(no_of_indices: usize) -> Result<Vec<I>, NnpsError> {
+    let mut head = Vec::new();
+    head.try_reserve_exact(no_of_indices)
+        .map_err(|_| NnpsError::CellCountOverflow)?;
+    head.resize(no_of_indices, I::NONE);
+    Ok(head)
+}
+
+/// Panic if the particles can not all be indexed with `I`.
+pub(crate) fn check_no_of_particles<I: ParticleIndex>(no_of_particles: usize) {
+    if let Err(error) = try_check_no_of_particles::<I>(no_of_particles) {
+        panic!("{}", error);
+    }
+}
+
+/// Cell list in `D = 1`, `2` or `3` dimensions. The domain is split into
+/// cells at least as large as the search range, and the particles of every
+/// cell are stacked in a linked list: `head` holds the last particle
+/// registered in every cell and `next` the particle below each particle, the
+/// end of a list being marked by `I::NONE`. Cells are numbered with the
+/// first axis varying fastest, or along a Morton curve, see `CellOrdering`.
+/// The particle coordinates are of type `T`,
+/// `f32` or `f64`, see `Real`, and the indices in `head` and `next` of type
+/// `I`, `usize` or the more compact `u32`, see `ParticleIndex`.
+///
+/// `NBS1D`, `NBS2D` and `NBS3D` are the grids in 1, 2 and 3 dimensions.
+#[derive(Debug, Clone)]
+pub struct CellGrid<const D: usize, T = f64, I = usize> {
+    pub head: Vec<I>,
+    pub next: Vec<I>,
+    /// Number of cells along every axis.
+    pub no_cells: [usize; D],
+    pub total_no_cells: usize,
+    /// Size of the cells along every axis.
+    pub cell_sizes: [f64; D],
+    /// The simulation domain.
+    pub domain: Aabb<D>,
+    pub periodic: [bool; D],
+    pub auto_fit: Option<AutoFit>,
+    pub strict: bool,
+    pub cell_count_policy: CellCountPolicy,
+    pub cell_ordering: CellOrdering,
+    // type of the particle coordinates
+    real: PhantomData<T>,
+}
+
+impl<const D: usize, T: Real, I: ParticleIndex> CellGrid<D, T, I> {
+    /// A grid covering `domain` with cells of `cell_size` along every axis.
+    pub fn from_aabb(domain: Aabb<D>, cell_size: f64) -> CellGrid<D, T, I> {
+        CellGrid::from_aabb_and_cell_sizes(domain, [cell_size; D])
+    }
+
+    /// Same as `from_aabb`, returning an error instead of panicking when the
+    /// domain or the cell size are invalid.
+    pub fn try_from_aabb(domain: Aabb<D>, cell_size: f64) -> Result<CellGrid<D, T, I>, NnpsError> {
+        CellGrid::try_from_aabb_and_cell_sizes(domain, [cell_size; D])
+    }
+
+    /// A grid covering `domain` with a separate cell size along every axis,
+    /// for domains with very different extents or resolutions per axis. The
+    /// search range along each axis is the cell size along that axis.
+    pub fn from_aabb_and_cell_sizes(domain: Aabb<D>, cell_sizes: [f64; D]) -> CellGrid<D, T, I> {
+        CellGrid::try_from_aabb_and_cell_sizes(domain, cell_sizes)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `from_aabb_and_cell_sizes`, returning an error instead of
+    /// panicking when the domain or the cell sizes are invalid.
+    pub fn try_from_aabb_and_cell_sizes(
+        domain: Aabb<D>,
+        cell_sizes: [f64; D],
+    ) -> Result<CellGrid<D, T, I>, NnpsError> {
+        CellGrid::try_from_aabb_with_policy(domain, cell_sizes, CellCountPolicy::default())
+    }
+
+    /// Same as `try_from_aabb_and_cell_sizes`, counting the cells under
+    /// `policy` from the start.
+    pub(crate) fn try_from_aabb_with_policy(
+        domain: Aabb<D>,
+        cell_sizes: [f64; D],
+        policy: CellCountPolicy,
+    ) -> Result<CellGrid<D, T, I>, NnpsError> {
+        // checked when the grid type is instantiated, `CellGrid<4>` does not
+        // build
+        const {
+            assert!(
+                D >= 1 && D <= 3,
+                "a cell grid can only be built in 1, 2 or 3 dimensions"
+            )
+        };
+        let mut no_cells = [0; D];
+        for (d, n) in no_cells.iter_mut().enumerate() {
+            *n = axis_no_cells(d, domain.min[d], domain.max[d], cell_sizes[d], policy)?;
+        }
+        let total_no_cells = total_no_cells(&no_cells)?;
+        Ok(CellGrid {
+            head: empty_head(total_no_cells)?,
+            next: vec![],
+            no_cells,
+            total_no_cells,
+            cell_sizes,
+            domain,
+            periodic: [false; D],
+            auto_fit: None,
+            strict: false,
+            cell_count_policy: policy,
+            cell_ordering: CellOrdering::RowMajor,
+            real: PhantomData,
+        })
+    }
+
+    pub fn initialize_next(&mut self, no_of_particles: usize) {
+        check_no_of_particles::<I>(no_of_particles);
+        self.next = vec![I::NONE; no_of_particles];
+    }
+
+    pub fn from_maximum_coordinate(max: f64, cell_size: f64) -> CellGrid<D, T, I> {
+        CellGrid::from_aabb(Aabb::from_maximum_coordinate(max), cell_size)
+    }
+
+    pub fn from_maximum_and_no_of_particles(
+        max: f64,
+        cell_size: f64,
+        no_of_particles: usize,
+    ) -> CellGrid<D, T, I> {
+        let mut grid = CellGrid::from_maximum_coordinate(max, cell_size);
+        grid.initialize_next(no_of_particles);
+        grid
+    }
+
+    /// Make the grid periodic along the axes set in `periodic`. On a periodic
+    /// axis particles are wrapped back into the domain when they are
+    /// registered, and the neighbour stencil wraps around, so particles close
+    /// to one face find their neighbours close to the opposite face.
+    pub fn set_periodic_axes(&mut self, periodic: [bool; D]) {
+        self.try_set_periodic_axes(periodic)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `set_periodic_axes`, returning an error instead of panicking
+    /// when a periodic axis has less than 3 cells.
+    pub fn try_set_periodic_axes(&mut self, periodic: [bool; D]) -> Result<(), NnpsError> {
+        for (d, &periodic) in periodic.iter().enumerate() {
+            // with less than three cells the stencil would visit the same
+            // cell twice on a periodic axis
+            let (min, max) = (self.domain.min[d], self.domain.max[d]);
+            let no_cells =
+                axis_no_cells(d, min, max, self.cell_sizes[d], CellCountPolicy::ClampEdge)?;
+            if periodic && no_cells < 3 {
+                return Err(NnpsError::TooFewPeriodicCells { axis: d });
+            }
+        }
+        self.resize_cells(periodic, self.cell_count_policy)
+    }
+
+    /// Choose how the number of cells is rounded, see `CellCountPolicy`.
+    /// The particles have to be registered again when the number of cells
+    /// changes.
+    pub fn set_cell_count_policy(&mut self, policy: CellCountPolicy) {
+        self.try_set_cell_count_policy(policy)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `set_cell_count_policy`, returning an error instead of
+    /// panicking when the rounded up cells would not fit in memory. The grid
+    /// is left untouched then.
+    pub fn try_set_cell_count_policy(&mut self, policy: CellCountPolicy) -> Result<(), NnpsError> {
+        self.resize_cells(self.periodic, policy)
+    }
+
+    /// Switch to the `periodic` axes and the cell count `policy`, recomputing
+    /// the number of cells from the domain and reallocating `head` if it
+    /// changed. Nothing changes when the new cells do not fit in memory.
+    fn resize_cells(
+        &mut self,
+        periodic: [bool; D],
+        policy: CellCountPolicy,
+    ) -> Result<(), NnpsError> {
+        let domain = self.domain;
+        let mut no_cells = [0; D];
+        for (d, n) in no_cells.iter_mut().enumerate() {
+            // the cells of a periodic axis have to tile the period
+            let policy = if periodic[d] {
+                CellCountPolicy::ClampEdge
+            } else {
+                policy
+            };
+            *n = axis_no_cells(d, domain.min[d], domain.max[d], self.cell_sizes[d], policy)?;
+        }
+        if no_cells != self.no_cells {
+            let total_no_cells = total_no_cells(&no_cells)?;
+            self.head = empty_head(no_of_indices(no_cells, self.cell_ordering)?)?;
+            self.no_cells = no_cells;
+            self.total_no_cells = total_no_cells;
+        }
+        self.periodic = periodic;
+        self.cell_count_policy = policy;
+        Ok(())
+    }
+
+    /// Number the cells along `ordering`, see `CellOrdering`. The particles
+    /// have to be registered again afterwards.
+    pub fn set_cell_ordering(&mut self, ordering: CellOrdering) {
+        self.try_set_cell_ordering(ordering)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `set_cell_ordering`, returning an error instead of panicking
+    /// when the cell indices would not fit in memory, or the cells along an
+    /// axis in a Morton key.
+    pub fn try_set_cell_ordering(&mut self, ordering: CellOrdering) -> Result<(), NnpsError> {
+        self.head = empty_head(no_of_indices(self.no_cells, ordering)?)?;
+        self.cell_ordering = ordering;
+        Ok(())
+    }
+
+    /// Turn the strict mode on or off. In strict mode
+    /// `register_particles_checked` returns an error as soon as one particle
+    /// is out of the domain or has a non finite position, so that a blow up
+    /// is caught straight away.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Turn the auto fit mode on or off, see `AutoFit`. Periodic axes keep
+    /// their limits.
+    pub fn set_auto_fit(&mut self, auto_fit: Option<AutoFit>) {
+        self.auto_fit = auto_fit;
+    }
+
+    /// With auto fit on, grow the domain to cover the particles. The grid is
+    /// left untouched when the grown grid would not fit in memory.
+    pub(crate) fn fit_domain(
+        &mut self,
+        coords: &[&[T]; 3],
+        no_of_particles: usize,
+    ) -> Result<(), NnpsError> {
+        let auto_fit = match self.auto_fit {
+            Some(auto_fit) => auto_fit,
+            None => return Ok(()),
+        };
+
+        // the bounding box of the particles with a finite position
+        let points = (0..no_of_particles).map(|i| particle_position::<T, D>(coords, i));
+        let particles = match Aabb::from_points(points) {
+            Some(particles) => particles,
+            None => return Ok(()),
+        };
+
+        let mut domain = self.domain;
+        let mut no_cells = self.no_cells;
+        let mut grown = false;
+        for d in (0..D).filter(|&d| !self.periodic[d]) {
+            let (min, max) = (domain.min[d], domain.max[d]);
+            // the registration keeps the particles within the domain limits,
+            // which may stop short of the last cell with a rounded up count
+            let (lo, hi) = (particles.min[d], particles.max[d]);
+            let cell_size = self.cell_sizes[d];
+            if let Some((min, max, n)) = auto_fit.fit_axis(min, max, lo, hi, cell_size)? {
+                domain.min[d] = min;
+                domain.max[d] = max;
+                no_cells[d] = n;
+                grown = true;
+            }
+        }
+        if !grown {
+            return Ok(());
+        }
+
+        let total_no_cells = total_no_cells(&no_cells)?;
+        let head = empty_head(no_of_indices(no_cells, self.cell_ordering)?)?;
+        self.domain = domain;
+        self.no_cells = no_cells;
+        self.total_no_cells = total_no_cells;
+        self.head = head;
+        Ok(())
+    }
+
+    /// Register the particles like `register_particles_to_nnps`, and report
+    /// the particles which were left out because they are out of the domain
+    /// or have a non finite position. In strict mode, see `set_strict`, any
+    /// lost particle turns the report into an error. More particles than `I`
+    /// can index are an error in any mode, and leave the grid untouched.
+    pub fn register_particles_checked(
+        &mut self,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+    ) -> Result<RegistrationReport, RegistrationError> {
+        try_check_no_of_particles::<I>(x.len())?;
+        let coords = [x, y, z];
+        self.fit_domain(&coords, x.len())?;
+        self.reserve_next(x.len());
+        let max_value = I::NONE;
+
+        // clear the previous stacked indices
+        for item in self.head.iter_mut() {
+            *item = max_value;
+        }
+        // similarly for next
+        for item in self.next.iter_mut() {
+            *item = max_value;
+        }
+
+        let mut lost = vec![];
+        for i in 0..x.len() {
+            let point = particle_position::<T, D>(&coords, i);
+            // eliminate the particles which are out of domain
+            if let Some(idx) = self.cell_index(point) {
+                self.next[i] = self.head[idx];
+                self.head[idx] = I::from_usize(i);
+            } else {
+                lost.push(i);
+            }
+        }
+
+        self.check_strict(lost_particles_report::<T, D>(&coords, lost))
+    }
+
+    /// Make room for `no_of_particles` in `next`, for the grids built without
+    /// `initialize_next`.
+    fn reserve_next(&mut self, no_of_particles: usize) {
+        if self.next.len() < no_of_particles {
+            self.next.resize(no_of_particles, I::NONE);
+        }
+    }
+
+    /// Turn `report` into an error in strict mode if some particles were
+    /// lost.
+    pub(crate) fn check_strict(
+        &self,
+        report: RegistrationReport,
+    ) -> Result<RegistrationReport, RegistrationError> {
+        if self.strict && !report.is_clean() {
+            return Err(RegistrationError::LostParticles { report });
+        }
+        Ok(report)
+    }
+
+    /// Parallel version of `register_particles_to_nnps`. The cells of the
+    /// particles are computed in parallel and the particles are binned with a
+    /// parallel stable sort on their cell, which keeps the particles of a
+    /// cell in increasing index order, so the linked lists come out exactly
+    /// as with the serial registration. In strict mode, panics like
+    /// `register_particles_to_nnps` when some particles are lost.
+    #[cfg(feature = "rayon")]
+    pub fn par_register_particles_to_nnps(&mut self, x: &[T], y: &[T], z: &[T]) {
+        check_no_of_particles::<I>(x.len());
+        let coords = [x, y, z];
+        self.fit_domain(&coords, x.len())
+            .unwrap_or_else(|e| panic!("{}", e));
+        self.reserve_next(x.len());
+        let max_value = usize::MAX;
+        let grid = &*self;
+
+        let cells: Vec<usize> = (0..x.len())
+            .into_par_iter()
+            .map(|i| {
+                grid.cell_index(particle_position(&coords, i))
+                    .unwrap_or(max_value)
+            })
+            .collect();
+        // eliminate the particles which are out of domain
+        let mut sorted: Vec<usize> = (0..x.len())
+            .into_par_iter()
+            .filter(|&i| cells[i] != max_value)
+            .collect();
+        sorted.par_sort_by_key(|&i| cells[i]);
+
+        self.head.par_iter_mut().for_each(|item| *item = I::NONE);
+        self.next.par_iter_mut().for_each(|item| *item = I::NONE);
+
+        // stack the particles of every cell, the last one ends up in head
+        let mut previous = max_value;
+        for &i in &sorted {
+            let idx = cells[i];
+            if previous != max_value && cells[previous] == idx {
+                self.next[i] = I::from_usize(previous);
+            }
+            self.head[idx] = I::from_usize(i);
+            previous = i;
+        }
+
+        if self.strict {
+            let lost: Vec<usize> = (0..x.len())
+                .into_par_iter()
+                .filter(|&i| cells[i] == max_value)
+                .collect();
+            if let Err(error) = self.check_strict(lost_particles_report::<T, D>(&coords, lost)) {
+                panic!("{}", error);
+            }
+        }
+    }
+
+    /// Cell coordinates of a point of the domain.
+    fn cell_coordinates(&self, point: [f64; D]) -> [usize; D] {
+        std::array::from_fn(|d| {
+            cell_coordinate(
+                point[d],
+                self.domain.min[d],
+                self.cell_sizes[d],
+                self.no_cells[d],
+            )
+        })
+    }
+
+    /// Index in `head` of the cell with coordinates `cell`.
+    pub(crate) fn flat_index(&self, cell: [usize; D]) -> usize {
+        self.cell_ordering.index(cell, self.no_cells)
+    }
+
+    /// Index in `head` of the cell holding `point`, or `None` if the point is
+    /// out of the domain. On periodic axes the point is wrapped back into the
+    /// domain first.
+    fn cell_index(&self, point: [f64; D]) -> Option<usize> {
+        self.cell_of(point).map(|cell| self.flat_index(cell))
+    }
+
+    /// Cell coordinates of `point`, or `None` if the point is out of the
+    /// domain. On periodic axes the point is wrapped back into the domain
+    /// first.
+    pub(crate) fn cell_of(&self, point: [f64; D]) -> Option<[usize; D]> {
+        let point = self.domain.wrap(point, self.periodic);
+        if self.domain.contains(point) && self.total_no_cells > 0 {
+            Some(self.cell_coordinates(point))
+        } else {
+            None
+        }
+    }
+
+    /// Index in `head` of the cell at `offset` from the cell `home`, along
+    /// with the periodic shift of its particles. `None` if the cell falls off
+    /// the grid on a non periodic axis.
+    pub(crate) fn neighbour_cell(
+        &self,
+        home: [isize; D],
+        offset: [isize; D],
+    ) -> Option<(usize, [f64; D])> {
+        let lengths = self.domain.lengths();
+        let mut cell = [0; D];
+        let mut shift = [0.; D];
+        for d in 0..D {
+            let (c, image) = wrap_cell(home[d] + offset[d], self.no_cells[d]);
+            if image != 0 && !self.periodic[d] {
+                return None;
+            }
+            cell[d] = c;
+            shift[d] = image as f64 * lengths[d];
+        }
+        Some((self.flat_index(cell), shift))
+    }
+
+    /// Same as `get_neighbours`, but every neighbour comes with the shift
+    /// which has to be added to its position, wrapped back into the domain,
+    /// to get its minimum image with respect to the query point. The shift is
+    /// zero unless the neighbour was found through a periodic boundary. Only
+    /// the first `D` coordinates of the query point are used.
+    pub fn get_neighbours_with_shifts(&self, x: T, y: T, z: T) -> Vec<(usize, [f64; D])> {
+        Neighbours::new(self, first_coordinates(x, y, z)).collect()
+    }
+
+    /// Every pair of registered particles sharing a cell or sitting in
+    /// neighbouring cells, visited once as `(i, j)` with `i < j`, using a half
+    /// stencil. Handy for symmetric interactions applying Newton's third law.
+    pub fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.pairs_with_shifts().map(|(i, j, _)| (i, j))
+    }
+
+    /// Same as `pairs`, but every pair comes with the shift which has to be
+    /// added to the position of `j`, wrapped back into the domain, to get its
+    /// minimum image with respect to `i`.
+    pub fn pairs_with_shifts(&self) -> impl Iterator<Item = (usize, usize, [f64; D])> + '_ {
+        Pairs::new(self).map(|(i, j, shift)| {
+            if i < j {
+                (i, j, shift)
+            } else {
+                (j, i, shift.map(|s| -s))
+            }
+        })
+    }
+
+    /// Same as `pairs`, keeping only the pairs closer than `h`, where
+    /// `coords` are the coordinate slices the particles were registered
+    /// with, one per axis.
+    pub fn pairs_within_coords<'a>(
+        &'a self,
+        coords: [&'a [T]; D],
+        h: T,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let h_squared = h.to_f64() * h.to_f64();
+        let position = move |i: usize| -> [f64; D] {
+            let point = std::array::from_fn(|d| coords[d][i].to_f64());
+            self.domain.wrap(point, self.periodic)
+        };
+        self.pairs_with_shifts()
+            .filter(move |&(i, j, shift)| {
+                let (pi, pj) = (position(i), position(j));
+                let r_squared: f64 = (0..D).map(|d| (pi[d] - pj[d] - shift[d]).powi(2)).sum();
+                r_squared <= h_squared
+            })
+            .map(|(i, j, _)| (i, j))
+    }
+}
+
+/// Lazy walk over the neighbours of a point, following the `head`/`next`
+/// lists of the stencil cells one particle at a time. Every particle comes
+/// with the periodic shift of the cell it was found in.
+///
+/// The stencil is built from the cell coordinates, and cells falling off the
+/// grid on a non periodic axis are skipped, so a query on the edge of the
+/// grid never picks up cells from the next row or plane.
+struct Neighbours<'a, const D: usize, T, I> {
+    grid: &'a CellGrid<D, T, I>,
+    home: [isize; D],
+    // position in the stencil of the next cell to visit
+    stencil_idx: usize,
+    particle_idx: I,
+    shift: [f64; D],
+}
+
+impl<'a, const D: usize, T: Real, I: ParticleIndex> Neighbours<'a, D, T, I> {
+    fn new(grid: &'a CellGrid<D, T, I>, point: [f64; D]) -> Self {
+        let point = grid.domain.wrap(point, grid.periodic);
+
+        // nothing to visit unless the point is in the simulation domain
+        let mut neighbours = Neighbours {
+            grid,
+            home: [0; D],
+            stencil_idx: stencil_len(D),
+            particle_idx: I::NONE,
+            shift: [0.; D],
+        };
+        if grid.domain.contains(point) && grid.total_no_cells > 0 {
+            neighbours.home = grid.cell_coordinates(point).map(|c| c as isize);
+            neighbours.stencil_idx = 0;
+        }
+        neighbours
+    }
+}
+
+impl<'a, const D: usize, T: Real, I: ParticleIndex> Iterator for Neighbours<'a, D, T, I> {
+    type Item = (usize, [f64; D]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let grid = self.grid;
+
+        // move on to the next cell of the stencil once the current one is
+        // exhausted
+        while self.particle_idx == I::NONE {
+            if self.stencil_idx == stencil_len(D) {
+                return None;
+            }
+            let offset = stencil_offset(self.stencil_idx);
+            self.stencil_idx += 1;
+
+            // off the grid on a non periodic axis
+            if let Some((idx, shift)) = grid.neighbour_cell(self.home, offset) {
+                self.shift = shift;
+                self.particle_idx = grid.head[idx];
+            }
+        }
+
+        let particle_idx = self.particle_idx;
+        self.particle_idx = grid.next[particle_idx.to_usize()];
+        Some((particle_idx.to_usize(), self.shift))
+    }
+}
+
+/// Lazy walk over the pairs of particles of the grid. For every home cell,
+/// each particle is paired with the particles following it in the home cell
+/// list and with all the particles of the half stencil cells.
+struct Pairs<'a, const D: usize, T, I> {
+    grid: &'a CellGrid<D, T, I>,
+    cell: usize,
+    home: [isize; D],
+    particle_i: I,
+    particle_j: I,
+    // 0 for the home cell, `k` for the `k`th cell of the stencil
+    stencil_idx: usize,
+    shift: [f64; D],
+}
+
+impl<'a, const D: usize, T: Real, I: ParticleIndex> Pairs<'a, D, T, I> {
+    fn new(grid: &'a CellGrid<D, T, I>) -> Self {
+        Pairs {
+            grid,
+            cell: 0,
+            home: [0; D],
+            particle_i: grid.head.first().copied().unwrap_or(I::NONE),
+            particle_j: I::NONE,
+            stencil_idx: 0,
+            shift: [0.; D],
+        }
+    }
+}
+
+impl<'a, const D: usize, T: Real, I: ParticleIndex> Iterator for Pairs<'a, D, T, I> {
+    type Item = (usize, usize, [f64; D]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let grid = self.grid;
+
+        while self.particle_j == I::NONE {
+            if self.particle_i == I::NONE {
+                // the home cell is done, move on to the next one
+                self.cell += 1;
+                if self.cell >= grid.head.len() {
+                    return None;
+                }
+                self.particle_i = grid.head[self.cell];
+                if self.particle_i == I::NONE {
+                    continue;
+                }
+                self.home = grid
+                    .cell_ordering
+                    .cell(self.cell, grid.no_cells)
+                    .map(|c| c as isize);
+                self.stencil_idx = 0;
+            } else if self.stencil_idx == 0 {
+                // the particles after `i` in the home cell
+                self.particle_j = grid.next[self.particle_i.to_usize()];
+                self.shift = [0.; D];
+                self.stencil_idx = 1;
+            } else if self.stencil_idx < stencil_len(D) {
+                let offset = stencil_offset(self.stencil_idx);
+                self.stencil_idx += 1;
+                if !in_half_stencil(&offset) {
+                    continue;
+                }
+                // off the grid on a non periodic axis
+                if let Some((idx, shift)) = grid.neighbour_cell(self.home, offset) {
+                    self.shift = shift;
+                    self.particle_j = grid.head[idx];
+                }
+            } else {
+                // all the partners of `i` are visited
+                self.particle_i = grid.next[self.particle_i.to_usize()];
+                self.stencil_idx = 0;
+            }
+        }
+
+        let particle_j = self.particle_j;
+        self.particle_j = grid.next[particle_j.to_usize()];
+        Some((
+            self.particle_i.to_usize(),
+            particle_j.to_usize(),
+            self.shift,
+        ))
+    }
+}
+
+impl<const D: usize, T: Real, I: ParticleIndex> NNPS<T> for CellGrid<D, T, I> {
+    /// Register the particles, see `register_particles_checked`. The lost
+    /// particles are left out silently, unless in strict mode where they
+    /// make the registration panic.
+    fn register_particles_to_nnps(&mut self, x: &[T], y: &[T], z: &[T]) {
+        if let Err(error) = self.register_particles_checked(x, y, z) {
+            panic!("{}", error);
+        }
+    }
+
+    fn periods(&self) -> [Option<f64>; 3] {
+        let lengths = self.domain.lengths();
+        let mut periods = [None; 3];
+        for d in (0..D).filter(|&d| self.periodic[d]) {
+            periods[d] = Some(lengths[d]);
+        }
+        periods
+    }
+
+    fn neighbours_iter(&self, x: T, y: T, z: T) -> impl Iterator<Item = usize> + '_ {
+        Neighbours::new(self, first_coordinates(x, y, z)).map(|(i, _)| i)
+    }
+
+    fn get_neighbours_into(&self, x: T, y: T, z: T, neighbours: &mut Vec<usize>) {
+        neighbours.clear();
+        neighbours.extend(self.neighbours_iter(x, y, z));
+    }
+
+    fn for_each_neighbour_within<F: FnMut(usize, T)>(
+        &self,
+        i: usize,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        h: T,
+        mut f: F,
+    ) {
+        let coords = [x, y, z];
+        let h_squared = h.to_f64() * h.to_f64();
+        let pi = self
+            .domain
+            .wrap(particle_position(&coords, i), self.periodic);
+
+        for (j, shift) in Neighbours::new(self, pi) {
+            let pj = self
+                .domain
+                .wrap(particle_position(&coords, j), self.periodic);
+            let r_squared: f64 = (0..D).map(|d| (pi[d] - pj[d] - shift[d]).powi(2)).sum();
+            if r_squared <= h_squared {
+                f(j, T::from_f64(r_squared.sqrt()));
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::cell_count::total_no_cells;
+use crate::morton;
+use crate::NnpsError;
+
+/// How the cell lists number their cells, that is where every cell sits in
+/// `head`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CellOrdering {
+    /// The first axis varies fastest, `nz * no_xy_cells + ny * no_x_cells +
+    /// nx` in 3D. No gap in `head`, but cells which are neighbours along the
+    /// last axis are a whole row or plane apart.
+    #[default]
+    RowMajor,
+    /// The index of a cell is the Morton key of its coordinates, see
+    /// `morton`, so spatially close cells are close in `head`. Unless the
+    /// number of cells along every axis is the same power of two, some
+    /// indices do not match any cell and `head` is longer than the number of
+    /// cells. Elongated grids, where the gaps would take most of `head`, are
+    /// rejected, see `MAX_MORTON_SPARSITY`.
+    Morton,
+}
+
+impl CellOrdering {
+    /// Index of the cell with coordinates `cell` in a grid with `no_cells`
+    /// cells along every axis.
+    pub fn index<const D: usize>(self, cell: [usize; D], no_cells: [usize; D]) -> usize {
+        match self {
+            CellOrdering::RowMajor => (0..D).rev().fold(0, |idx, d| idx * no_cells[d] + cell[d]),
+            CellOrdering::Morton => morton::encode(cell) as usize,
+        }
+    }
+
+    /// Coordinates of the cell at index `idx`, the inverse of `index`.
+    pub fn cell<const D: usize>(self, idx: usize, no_cells: [usize; D]) -> [usize; D] {
+        match self {
+            CellOrdering::RowMajor => {
+                let mut rest = idx;
+                std::array::from_fn(|d| {
+                    let c = rest % no_cells[d];
+                    rest /= no_cells[d];
+                    c
+                })
+            }
+            CellOrdering::Morton => morton::decode(idx as u64),
+        }
+    }
+}
+
+/// Largest ratio of the length of `head` to the number of cells accepted
+/// with the Morton ordering. The keys of a grid much longer along one axis
+/// than along the others are spread over the square or cube around it, so
+/// `head` would be mostly made of indices matching no cell.
+pub const MAX_MORTON_SPARSITY: usize = 64;
+
+/// Length of `head` for a grid with `no_cells` cells along every axis, one
+/// past the largest cell index.
+pub(crate) fn no_of_indices<const D: usize>(
+    no_cells: [usize; D],
+    ordering: CellOrdering,
+) -> Result<usize, NnpsError> {
+    let total = total_no_cells(&no_cells)?;
+    if ordering == CellOrdering::RowMajor || total == 0 {
+        return Ok(total);
+    }
+    if no_cells.iter().any(|&n| n > morton::max_axis_no_cells(D)) {
+        return Err(NnpsError::CellCountOverflow);
+    }
+    let last = ordering.index(no_cells.map(|n| n - 1), no_cells);
+    let no_of_indices = last.checked_add(1).ok_or(NnpsError::CellCountOverflow)?;
+    if no_of_indices / MAX_MORTON_SPARSITY > total {
+        return Err(NnpsError::CellCountOverflow);
+    }
+    total_no_cells(&[no_of_indices])
+}
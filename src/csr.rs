@@ -0,0 +1,32 @@
+/// Neighbour lists of all the particles in compressed sparse row format. The
+/// neighbours of particle `i` are `indices[offsets[i]..offsets[i + 1]]`, and
+/// their distances, when requested, sit at the same positions in `distances`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrNeighbourList<T = f64> {
+    pub offsets: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub distances: Option<Vec<T>>,
+}
+
+impl<T> CsrNeighbourList<T> {
+    /// Number of particles the lists were built for.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Neighbours of particle `i`.
+    pub fn neighbours(&self, i: usize) -> &[usize] {
+        &self.indices[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    /// Distances to the neighbours of particle `i`, if they were computed.
+    pub fn distances(&self, i: usize) -> Option<&[T]> {
+        self.distances
+            .as_ref()
+            .map(|distances| &distances[self.offsets[i]..self.offsets[i + 1]])
+    }
+}
@@ -0,0 +1,61 @@
+use std::error::Error;
+use std::fmt;
+
+/// Errors raised when building a neighbour search structure from invalid
+/// parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NnpsError {
+    /// The cell size is not positive, or larger than the domain along
+    /// `axis` (0 for `x`, 1 for `y`, 2 for `z`).
+    InvalidCellSize { cell_size: f64, axis: usize },
+    /// The lower limit along `axis` is not below the upper one.
+    InvertedBounds { axis: usize, min: f64, max: f64 },
+    /// A limit or the cell size is NaN or infinite.
+    NonFinite,
+    /// The number of cells does not fit in memory.
+    CellCountOverflow,
+    /// A periodic axis has less than 3 cells.
+    TooFewPeriodicCells { axis: usize },
+    /// The limits along `axis` were not given to the builder.
+    MissingLimits { axis: usize },
+    /// The cell size was not given to the builder.
+    MissingCellSize,
+    /// More particles than the index type of `head` and `next` can address.
+    TooManyParticles { no_of_particles: usize, max: usize },
+}
+
+impl fmt::Display for NnpsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NnpsError::InvalidCellSize { cell_size, axis } => write!(
+                f,
+                "cell size {} is not positive or larger than the domain along axis {}",
+                cell_size, axis
+            ),
+            NnpsError::InvertedBounds { axis, min, max } => write!(
+                f,
+                "the lower limit {} is not below the upper limit {} along axis {}",
+                min, max, axis
+            ),
+            NnpsError::NonFinite => write!(f, "the limits and the cell size have to be finite"),
+            NnpsError::CellCountOverflow => write!(f, "the number of cells is too large"),
+            NnpsError::TooFewPeriodicCells { axis } => {
+                write!(f, "the periodic axis {} needs at least 3 cells", axis)
+            }
+            NnpsError::MissingLimits { axis } => {
+                write!(f, "the limits along axis {} are missing", axis)
+            }
+            NnpsError::MissingCellSize => write!(f, "the cell size is missing"),
+            NnpsError::TooManyParticles {
+                no_of_particles,
+                max,
+            } => write!(
+                f,
+                "{} particles do not fit in indices addressing at most {} particles",
+                no_of_particles, max
+            ),
+        }
+    }
+}
+
+impl Error for NnpsError {}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::particle_index::ParticleIndex;
+use crate::real::Real;
+use crate::NNPS;
+
+/// Cell list without domain limits. Cells are keyed on their integer
+/// coordinates in a hash map, so only the occupied cells take memory and
+/// particles can go anywhere, which suits free surface flows where particles
+/// leave any preset box.
+///
+/// Like `CellGrid`, the particles of a cell are stacked in a linked
+/// list, `head` holding the last particle of every occupied cell and `next`
+/// the particle below each one.
+///
+/// The particle coordinates are of type `T`, `f32` or `f64`, see `Real`.
+#[derive(Debug, Clone)]
+pub struct HashGrid<T = f64> {
+    pub head: HashMap<[i64; 3], usize>,
+    pub next: Vec<usize>,
+    pub dim: usize,
+    pub cell_size: f64,
+    // type of the particle coordinates
+    real: PhantomData<T>,
+}
+
+impl<T: Real> HashGrid<T> {
+    /// A hashed grid in `dim = 2` or `dim = 3` dimensions with cells of
+    /// `cell_size`.
+    pub fn new(dim: usize, cell_size: f64) -> HashGrid<T> {
+        if dim != 2 && dim != 3 {
+            panic!("a hash grid can only be built in 2 or 3 dimensions");
+        }
+        if cell_size.is_nan() || cell_size <= 0. {
+            panic!("the cell size has to be positive");
+        }
+        HashGrid {
+            head: HashMap::new(),
+            next: vec![],
+            dim,
+            cell_size,
+            real: PhantomData,
+        }
+    }
+
+    /// Integer coordinates of the cell holding the point `(x, y, z)`, `None`
+    /// if the point is not finite. The coordinates saturate at the bounds of
+    /// `i64` for points that far away.
+    fn cell_key(&self, x: f64, y: f64, z: f64) -> Option<[i64; 3]> {
+        let z = if self.dim == 2 { 0. } else { z };
+        if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+            return None;
+        }
+        Some([
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+            (z / self.cell_size).floor() as i64,
+        ])
+    }
+
+    /// Number of cells holding at least one particle.
+    pub fn no_occupied_cells(&self) -> usize {
+        self.head.len()
+    }
+}
+
+/// Lazy walk over the particles of the 9 (2D) or 27 (3D) cells around a
+/// point.
+struct Neighbours<'a, T> {
+    grid: &'a HashGrid<T>,
+    key: [i64; 3],
+    // position of the next cell to visit in the `3^dim` stencil
+    stencil_idx: usize,
+    stencil_len: usize,
+    particle_idx: usize,
+}
+
+impl<'a, T: Real> Neighbours<'a, T> {
+    fn new(grid: &'a HashGrid<T>, x: f64, y: f64, z: f64) -> Self {
+        let stencil_len = if grid.dim == 2 { 9 } else { 27 };
+        let (key, stencil_idx) = match grid.cell_key(x, y, z) {
+            Some(key) => (key, 0),
+            // nothing to visit around a non finite point
+            None => ([0; 3], stencil_len),
+        };
+        Neighbours {
+            grid,
+            key,
+            stencil_idx,
+            stencil_len,
+            particle_idx: usize::NONE,
+        }
+    }
+}
+
+impl<'a, T: Real> Iterator for Neighbours<'a, T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // move on to the next occupied cell of the stencil once the current
+        // one is exhausted
+        while self.particle_idx == usize::NONE {
+            if self.stencil_idx == self.stencil_len {
+                return None;
+            }
+            let offset = self.stencil_idx as i64;
+            self.stencil_idx += 1;
+
+            let key = (
+                self.key[0].checked_add(offset % 3 - 1),
+                self.key[1].checked_add(offset / 3 % 3 - 1),
+                self.key[2].checked_add(offset / 9 - if self.grid.dim == 2 { 0 } else { 1 }),
+            );
+            // the keys of far away points saturate, and the cells past them
+            // do not exist
+            if let (Some(k0), Some(k1), Some(k2)) = key {
+                if let Some(&idx) = self.grid.head.get(&[k0, k1, k2]) {
+                    self.particle_idx = idx;
+                }
+            }
+        }
+
+        let particle_idx = self.particle_idx;
+        self.particle_idx = self.grid.next[particle_idx];
+        Some(particle_idx)
+    }
+}
+
+impl<T: Real> NNPS<T> for HashGrid<T> {
+    fn register_particles_to_nnps(&mut self, x: &[T], y: &[T], z: &[T]) {
+        let max_value = usize::NONE;
+
+        // forget the previous cells, keeping the allocation of the map
+        self.head.clear();
+        self.next.clear();
+        self.next.resize(x.len(), max_value);
+
+        for i in 0..x.len() {
+            let zi = if self.dim == 2 { 0. } else { z[i].to_f64() };
+            // particles with a non finite position belong to no cell
+            if let Some(key) = self.cell_key(x[i].to_f64(), y[i].to_f64(), zi) {
+                let head = self.head.entry(key).or_insert(max_value);
+                self.next[i] = *head;
+                *head = i;
+            }
+        }
+    }
+
+    fn neighbours_iter(&self, x: T, y: T, z: T) -> impl Iterator<Item = usize> + '_ {
+        Neighbours::new(self, x.to_f64(), y.to_f64(), z.to_f64())
+    }
+
+    fn get_neighbours_into(&self, x: T, y: T, z: T, neighbours: &mut Vec<usize>) {
+        neighbours.clear();
+        neighbours.extend(self.neighbours_iter(x, y, z));
+    }
+
+    fn for_each_neighbour_within<F: FnMut(usize, T)>(
+        &self,
+        i: usize,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        h: T,
+        mut f: F,
+    ) {
+        let h_squared = h.to_f64() * h.to_f64();
+        let position = |j: usize| {
+            let zj = if self.dim == 2 { 0. } else { z[j].to_f64() };
+            [x[j].to_f64(), y[j].to_f64(), zj]
+        };
+        let pi = position(i);
+        for j in Neighbours::new(self, pi[0], pi[1], pi[2]) {
+            let pj = position(j);
+            let dx = pi[0] - pj[0];
+            let dy = pi[1] - pj[1];
+            let dz = pi[2] - pj[2];
+            let r_squared = dx * dx + dy * dy + dz * dz;
+            if r_squared <= h_squared {
+                f(j, T::from_f64(r_squared.sqrt()));
+            }
+        }
+    }
+}
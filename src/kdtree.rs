@@ -0,0 +1,316 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::real::Real;
+use crate::NNPS;
+
+/// Number of particles below which a node is not split any further.
+const LEAF_SIZE: usize = 8;
+
+/// A node of a `KdTree`, owning the particles `indices[start..end]` of the
+/// tree. Inner nodes split them at `split_value` along `split_dim`, the left
+/// child holding the coordinates below it and the right child the ones
+/// above.
+#[derive(Debug, Clone)]
+pub struct KdNode {
+    pub start: usize,
+    pub end: usize,
+    pub split_dim: usize,
+    pub split_value: f64,
+    /// Positions of the left and right children in `nodes`, `None` for the
+    /// leaves.
+    pub children: Option<(usize, usize)>,
+}
+
+/// k-d tree for neighbour searches in 2D or 3D. Besides the fixed radius
+/// queries of `NNPS` it answers k nearest neighbour queries, which the cell
+/// lists can not do.
+///
+/// Like the octree, `get_neighbours` only returns the particles within
+/// `radius` of the query point.
+///
+/// The positions are stored with the precision `T` of the coordinates, while
+/// the tree itself is built in `f64`.
+#[derive(Debug, Clone)]
+pub struct KdTree<T = f64> {
+    /// Positions of the registered particles, `z` is zero in 2D.
+    pub points: Vec<[T; 3]>,
+    /// Indices of the particles, ordered so that every node owns a
+    /// contiguous range.
+    pub indices: Vec<usize>,
+    /// The root, if any, is the first node.
+    pub nodes: Vec<KdNode>,
+    pub dim: usize,
+    pub radius: T,
+}
+
+/// A candidate of a k nearest neighbour query, ordered by distance so that
+/// the farthest candidate sits on top of the heap.
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    distance_squared: f64,
+    idx: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_squared
+            .total_cmp(&other.distance_squared)
+            .then(self.idx.cmp(&other.idx))
+    }
+}
+
+impl<T: Real> KdTree<T> {
+    /// A k-d tree in `dim = 2` or `dim = 3` dimensions, answering
+    /// `get_neighbours` with the particles within `radius`.
+    pub fn new(dim: usize, radius: T) -> KdTree<T> {
+        if dim != 2 && dim != 3 {
+            panic!("a k-d tree can only be built in 2 or 3 dimensions");
+        }
+        KdTree {
+            points: vec![],
+            indices: vec![],
+            nodes: vec![],
+            dim,
+            radius,
+        }
+    }
+
+    /// Indices of the registered particles within `radius` of the point
+    /// `(x, y, z)`.
+    pub fn get_neighbours_in_radius(&self, x: T, y: T, z: T, radius: T) -> Vec<usize> {
+        WithinRadius::new(self, [x, y, z].map(T::to_f64), radius.to_f64()).collect()
+    }
+
+    /// The `k` registered particles closest to the point `(x, y, z)`, nearest
+    /// first. Fewer are returned if less than `k` particles are registered.
+    pub fn get_k_nearest(&self, x: T, y: T, z: T, k: usize) -> Vec<usize> {
+        self.get_k_nearest_with_distances(x, y, z, k)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Same as `get_k_nearest`, along with the distance of every particle.
+    pub fn get_k_nearest_with_distances(&self, x: T, y: T, z: T, k: usize) -> Vec<(usize, T)> {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        if k > 0 && !self.nodes.is_empty() {
+            let z = if self.dim == 2 { 0. } else { z.to_f64() };
+            self.k_nearest(0, [x.to_f64(), y.to_f64(), z], k, &mut heap);
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|c| (c.idx, T::from_f64(c.distance_squared.sqrt())))
+            .collect()
+    }
+
+    /// Position of the registered particle `i` in `f64`.
+    fn position(&self, i: usize) -> [f64; 3] {
+        self.points[i].map(T::to_f64)
+    }
+
+    fn k_nearest(&self, node: usize, point: [f64; 3], k: usize, heap: &mut BinaryHeap<Candidate>) {
+        let node = &self.nodes[node];
+        match node.children {
+            None => {
+                for &i in &self.indices[node.start..node.end] {
+                    let candidate = Candidate {
+                        distance_squared: distance_squared(point, self.position(i)),
+                        idx: i,
+                    };
+                    if heap.len() < k {
+                        heap.push(candidate);
+                    } else if candidate < *heap.peek().unwrap() {
+                        heap.pop();
+                        heap.push(candidate);
+                    }
+                }
+            }
+            Some((left, right)) => {
+                // visit the side of the point first, the other side only if
+                // it can hold something closer than the current candidates
+                let diff = point[node.split_dim] - node.split_value;
+                let (near, far) = if diff < 0. {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                self.k_nearest(near, point, k, heap);
+                if heap.len() < k || diff * diff < heap.peek().unwrap().distance_squared {
+                    self.k_nearest(far, point, k, heap);
+                }
+            }
+        }
+    }
+
+    /// Build the subtree owning `indices[start..end]` and return its position
+    /// in `nodes`.
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push(KdNode {
+            start,
+            end,
+            split_dim: 0,
+            split_value: 0.,
+            children: None,
+        });
+        if end - start <= LEAF_SIZE {
+            return node;
+        }
+
+        // split along the axis with the largest spread, at the median
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for &i in &self.indices[start..end] {
+            for (d, &v) in self.position(i).iter().enumerate() {
+                min[d] = min[d].min(v);
+                max[d] = max[d].max(v);
+            }
+        }
+        let split_dim = (0..self.dim)
+            .max_by(|&a, &b| (max[a] - min[a]).total_cmp(&(max[b] - min[b])))
+            .unwrap();
+
+        let mid = (start + end) / 2;
+        let points = &self.points;
+        self.indices[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+            let (a, b) = (points[a][split_dim].to_f64(), points[b][split_dim].to_f64());
+            a.total_cmp(&b)
+        });
+        let split_value = self.position(self.indices[mid])[split_dim];
+
+        let left = self.build(start, mid);
+        let right = self.build(mid, end);
+        let node_ref = &mut self.nodes[node];
+        node_ref.split_dim = split_dim;
+        node_ref.split_value = split_value;
+        node_ref.children = Some((left, right));
+        node
+    }
+}
+
+fn distance_squared(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let dx = a[0] - b[0];
+    let dy = a[1] - b[1];
+    let dz = a[2] - b[2];
+    dx * dx + dy * dy + dz * dz
+}
+
+/// Lazy walk over the particles within a radius of a point. Subtrees lying
+/// entirely on the far side of a split plane are skipped.
+struct WithinRadius<'a, T> {
+    tree: &'a KdTree<T>,
+    point: [f64; 3],
+    radius: f64,
+    // nodes left to visit
+    stack: Vec<usize>,
+    // particles of the current leaf left to check
+    leaf: &'a [usize],
+}
+
+impl<'a, T: Real> WithinRadius<'a, T> {
+    fn new(tree: &'a KdTree<T>, point: [f64; 3], radius: f64) -> Self {
+        let [x, y, z] = point;
+        let z = if tree.dim == 2 { 0. } else { z };
+        let stack = if tree.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        WithinRadius {
+            tree,
+            point: [x, y, z],
+            radius,
+            stack,
+            leaf: &[],
+        }
+    }
+}
+
+impl<'a, T: Real> Iterator for WithinRadius<'a, T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tree = self.tree;
+        let radius_squared = self.radius * self.radius;
+        loop {
+            while let Some((&i, rest)) = self.leaf.split_first() {
+                self.leaf = rest;
+                if distance_squared(self.point, tree.position(i)) <= radius_squared {
+                    return Some(i);
+                }
+            }
+
+            let node = &tree.nodes[self.stack.pop()?];
+            match node.children {
+                None => self.leaf = &tree.indices[node.start..node.end],
+                Some((left, right)) => {
+                    let p = self.point[node.split_dim];
+                    if p + self.radius >= node.split_value {
+                        self.stack.push(right);
+                    }
+                    if p - self.radius <= node.split_value {
+                        self.stack.push(left);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Real> NNPS<T> for KdTree<T> {
+    fn register_particles_to_nnps(&mut self, x: &[T], y: &[T], z: &[T]) {
+        let dim = self.dim;
+        let zero = T::from_f64(0.);
+        self.points = (0..x.len())
+            .map(|i| [x[i], y[i], if dim == 2 { zero } else { z[i] }])
+            .collect();
+
+        // particles with a non finite position can not be ordered, leave
+        // them out
+        let points = &self.points;
+        self.indices = (0..x.len())
+            .filter(|&i| points[i].iter().all(|v| v.is_finite()))
+            .collect();
+
+        self.nodes.clear();
+        if !self.indices.is_empty() {
+            self.build(0, self.indices.len());
+        }
+    }
+
+    fn neighbours_iter(&self, x: T, y: T, z: T) -> impl Iterator<Item = usize> + '_ {
+        WithinRadius::new(self, [x, y, z].map(T::to_f64), self.radius.to_f64())
+    }
+
+    fn get_neighbours_into(&self, x: T, y: T, z: T, neighbours: &mut Vec<usize>) {
+        neighbours.clear();
+        neighbours.extend(self.neighbours_iter(x, y, z));
+    }
+
+    fn for_each_neighbour_within<F: FnMut(usize, T)>(
+        &self,
+        i: usize,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        h: T,
+        mut f: F,
+    ) {
+        // the tree has no fixed search range, any `h` can be used
+        let zi = if self.dim == 2 { 0. } else { z[i].to_f64() };
+        let point = [x[i].to_f64(), y[i].to_f64(), zi];
+        for j in WithinRadius::new(self, point, h.to_f64()) {
+            let r = distance_squared(point, self.position(j)).sqrt();
+            f(j, T::from_f64(r));
+        }
+    }
+}
@@ -1,11 +1,160 @@
+// lints raised by the original bst and particle_array code
+#![allow(clippy::match_ref_pats, clippy::needless_return)]
+
+pub mod aabb;
+pub mod auto_fit;
+pub mod builder;
+pub mod cell_count;
+pub mod cell_grid;
+pub mod cell_ordering;
+pub mod csr;
+mod error;
+pub mod hash_grid;
+pub mod kdtree;
+pub mod morton;
+pub mod multi_array;
+pub mod nbs1d;
 pub mod nbs2d;
 pub mod nbs3d;
+pub mod octree_nnps;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+mod periodic;
 pub mod bst;
 pub mod particle_array;
+pub mod particle_index;
 pub mod prelude;
+pub mod real;
+pub mod registration;
+pub mod reorder;
+pub mod sorted_grid;
+pub mod verlet;
+
+use crate::csr::CsrNeighbourList;
+pub use crate::error::NnpsError;
+use crate::real::Real;
+
+/// Neighbour search over particles whose coordinates are of type `T`, `f64`
+/// unless stated otherwise.
+///
+/// The trait can be used as `dyn NNPS` to swap backends at run time, through
+/// `register_particles_to_nnps`, `get_neighbours` and `get_neighbours_into`.
+/// The iterator and closure based methods need the concrete backend.
+pub trait NNPS<T: Real = f64> {
+    fn register_particles_to_nnps(&mut self, x: &[T], y: &[T], z: &[T]);
+
+    /// Lazily walk over the neighbours of the point `(x, y, z)`, without
+    /// allocating.
+    fn neighbours_iter(&self, x: T, y: T, z: T) -> impl Iterator<Item = usize> + '_
+    where
+        Self: Sized;
+
+    fn get_neighbours(&self, x: T, y: T, z: T) -> Vec<usize> {
+        let mut neighbours = vec![];
+        self.get_neighbours_into(x, y, z, &mut neighbours);
+        neighbours
+    }
+
+    /// Same as `get_neighbours`, but the neighbours are written into
+    /// `neighbours`, which is cleared first, so that one buffer can be reused
+    /// for all the queries of a time step.
+    fn get_neighbours_into(&self, x: T, y: T, z: T, neighbours: &mut Vec<usize>);
+
+    /// Period along every axis, `None` on the axes which are not periodic.
+    /// The neighbours found through a periodic face are at the minimum image
+    /// distance of the query point.
+    fn periods(&self) -> [Option<f64>; 3] {
+        [None; 3]
+    }
+
+    /// Call `f` with the index and the distance of every registered particle
+    /// within a distance `h` of particle `i`, the particle itself included.
+    /// `x`, `y` and `z` are the coordinates the particles were registered
+    /// with. `h` must not be larger than the search range of the backend
+    /// (the smallest cell size for the cell lists).
+    fn for_each_neighbour_within<F: FnMut(usize, T)>(
+        &self,
+        i: usize,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        h: T,
+        mut f: F,
+    ) where
+        Self: Sized,
+    {
+        let h_squared = h * h;
+        for j in self.neighbours_iter(x[i], y[i], z[i]) {
+            let dx = x[i] - x[j];
+            let dy = y[i] - y[j];
+            let dz = z[i] - z[j];
+            let r_squared = dx * dx + dy * dy + dz * dz;
+            if r_squared <= h_squared {
+                f(j, r_squared.sqrt());
+            }
+        }
+    }
+
+    /// Indices of the registered particles within a distance `h` of particle
+    /// `i`, see `for_each_neighbour_within`. The query particle itself is
+    /// left out when `exclude_self` is set.
+    fn get_neighbours_within(
+        &self,
+        i: usize,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        h: T,
+        exclude_self: bool,
+    ) -> Vec<usize>
+    where
+        Self: Sized,
+    {
+        let mut neighbours = vec![];
+        self.for_each_neighbour_within(i, x, y, z, h, |j, _| {
+            if !(exclude_self && j == i) {
+                neighbours.push(j);
+            }
+        });
+        neighbours
+    }
+
+    /// Neighbours within `h` of every registered particle, stored in
+    /// compressed sparse row format. The distances are filled in only when
+    /// `with_distances` is set.
+    fn get_neighbours_csr(
+        &self,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        h: T,
+        exclude_self: bool,
+        with_distances: bool,
+    ) -> CsrNeighbourList<T>
+    where
+        Self: Sized,
+    {
+        let mut offsets = Vec::with_capacity(x.len() + 1);
+        let mut indices = vec![];
+        let mut distances = vec![];
 
+        offsets.push(0);
+        for i in 0..x.len() {
+            self.for_each_neighbour_within(i, x, y, z, h, |j, r| {
+                if !(exclude_self && j == i) {
+                    indices.push(j);
+                    if with_distances {
+                        distances.push(r);
+                    }
+                }
+            });
+            offsets.push(indices.len());
+        }
 
-pub trait NNPS {
-    fn register_particles_to_nnps(&mut self, x: &[f64], y: &[f64], z: &[f64]);
-    fn get_neighbours(&self, x: f64, y: f64, z: f64) -> Vec<usize>;
+        CsrNeighbourList {
+            offsets,
+            indices,
+            distances: if with_distances { Some(distances) } else { None },
+        }
+    }
 }
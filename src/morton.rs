@@ -0,0 +1,114 @@
+//! Morton (Z-order) keys of cell coordinates. The bits of the coordinates
+//! are interleaved, `x` taking the lowest bit, so cells close in space tend
+//! to get close keys.
+
+/// Spread the bits of `v` so that bit `b` moves to bit `2 * b`.
+fn part_1_by_1(v: u32) -> u64 {
+    let mut v = v as u64;
+    v = (v | (v << 16)) & 0x0000_ffff_0000_ffff;
+    v = (v | (v << 8)) & 0x00ff_00ff_00ff_00ff;
+    v = (v | (v << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    (v | (v << 1)) & 0x5555_5555_5555_5555
+}
+
+/// Spread the lowest 21 bits of `v` so that bit `b` moves to bit `3 * b`.
+fn part_1_by_2(v: u32) -> u64 {
+    let mut v = v as u64 & 0x1f_ffff;
+    v = (v | (v << 32)) & 0x001f_0000_0000_ffff;
+    v = (v | (v << 16)) & 0x001f_0000_ff00_00ff;
+    v = (v | (v << 8)) & 0x100f_00f0_0f00_f00f;
+    v = (v | (v << 4)) & 0x10c3_0c30_c30c_30c3;
+    (v | (v << 2)) & 0x1249_2492_4924_9249
+}
+
+/// Morton key of the 2D cell `(x, y)`.
+pub fn encode_2d(x: u32, y: u32) -> u64 {
+    part_1_by_1(x) | (part_1_by_1(y) << 1)
+}
+
+/// Morton key of the 3D cell `(x, y, z)`. Only the lowest 21 bits of every
+/// coordinate are kept.
+pub fn encode_3d(x: u32, y: u32, z: u32) -> u64 {
+    part_1_by_2(x) | (part_1_by_2(y) << 1) | (part_1_by_2(z) << 2)
+}
+
+/// Morton key of a cell in `D = 1`, `2` or `3` dimensions, the key of a 1D
+/// cell being its coordinate.
+pub fn encode<const D: usize>(cell: [usize; D]) -> u64 {
+    match D {
+        1 => cell[0] as u64,
+        2 => encode_2d(cell[0] as u32, cell[1] as u32),
+        3 => encode_3d(cell[0] as u32, cell[1] as u32, cell[2] as u32),
+        _ => panic!("morton keys are only defined in 1, 2 or 3 dimensions"),
+    }
+}
+
+/// Gather every second bit of `v` back into the lowest 32 bits, the inverse
+/// of `part_1_by_1`.
+fn compact_1_by_1(v: u64) -> u32 {
+    let mut v = v & 0x5555_5555_5555_5555;
+    v = (v | (v >> 1)) & 0x3333_3333_3333_3333;
+    v = (v | (v >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | (v >> 4)) & 0x00ff_00ff_00ff_00ff;
+    v = (v | (v >> 8)) & 0x0000_ffff_0000_ffff;
+    ((v | (v >> 16)) & 0x0000_0000_ffff_ffff) as u32
+}
+
+/// Gather every third bit of `v` back into the lowest 21 bits, the inverse
+/// of `part_1_by_2`.
+fn compact_1_by_2(v: u64) -> u32 {
+    let mut v = v & 0x1249_2492_4924_9249;
+    v = (v | (v >> 2)) & 0x10c3_0c30_c30c_30c3;
+    v = (v | (v >> 4)) & 0x100f_00f0_0f00_f00f;
+    v = (v | (v >> 8)) & 0x001f_0000_ff00_00ff;
+    v = (v | (v >> 16)) & 0x001f_0000_0000_ffff;
+    ((v | (v >> 32)) & 0x1f_ffff) as u32
+}
+
+/// Cell `(x, y)` of the 2D Morton key `key`.
+pub fn decode_2d(key: u64) -> (u32, u32) {
+    (compact_1_by_1(key), compact_1_by_1(key >> 1))
+}
+
+/// Cell `(x, y, z)` of the 3D Morton key `key`.
+pub fn decode_3d(key: u64) -> (u32, u32, u32) {
+    (
+        compact_1_by_2(key),
+        compact_1_by_2(key >> 1),
+        compact_1_by_2(key >> 2),
+    )
+}
+
+/// Cell in `D = 1`, `2` or `3` dimensions of the Morton key `key`, the
+/// inverse of `encode`.
+pub fn decode<const D: usize>(key: u64) -> [usize; D] {
+    let mut cell = [0; D];
+    match D {
+        1 => cell[0] = key as usize,
+        2 => {
+            let (x, y) = decode_2d(key);
+            cell[0] = x as usize;
+            cell[1] = y as usize;
+        }
+        3 => {
+            let (x, y, z) = decode_3d(key);
+            cell[0] = x as usize;
+            cell[1] = y as usize;
+            cell[2] = z as usize;
+        }
+        _ => panic!("morton keys are only defined in 1, 2 or 3 dimensions"),
+    }
+    cell
+}
+
+/// Largest number of cells along an axis whose coordinates all fit in a
+/// Morton key in `dim` dimensions.
+pub fn max_axis_no_cells(dim: usize) -> usize {
+    match dim {
+        1 => usize::MAX,
+        2 => (u32::MAX as usize).saturating_add(1),
+        3 => 1 << 21,
+        _ => panic!("morton keys are only defined in 1, 2 or 3 dimensions"),
+    }
+}
@@ -0,0 +1,142 @@
+use crate::cell_grid::CellGrid;
+use crate::particle_array::ParticleArray;
+use crate::particle_index::ParticleIndex;
+use crate::NNPS;
+
+/// Several particle arrays registered in one cell list, each tagged with an
+/// ID, so that the neighbours of a particle of one array (the destination)
+/// can be searched among the particles of another array (the source), say
+/// the boundary particles around a fluid particle, without one grid per pair
+/// of arrays.
+///
+/// The positions of the arrays are copied one after the other into `x`, `y`
+/// and `z`, the particles of the array at position `k` in `ids` taking the
+/// indices `offsets[k]..offsets[k + 1]` in `grid`. Neighbours are returned
+/// as `(array_id, index)` pairs, `index` being the index of the particle in
+/// its own array.
+#[derive(Debug, Clone)]
+pub struct MultiArrayGrid<const D: usize, I = usize> {
+    pub grid: CellGrid<D, f64, I>,
+    /// IDs of the registered arrays, in registration order.
+    pub ids: Vec<usize>,
+    /// Index in `grid` of the first particle of every array, followed by the
+    /// total number of particles.
+    pub offsets: Vec<usize>,
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub z: Vec<f64>,
+}
+
+impl<const D: usize, I: ParticleIndex> MultiArrayGrid<D, I> {
+    /// Search over the cells of `grid`. Nothing is registered until
+    /// `register_arrays`.
+    pub fn new(grid: CellGrid<D, f64, I>) -> MultiArrayGrid<D, I> {
+        MultiArrayGrid {
+            grid,
+            ids: vec![],
+            offsets: vec![0],
+            x: vec![],
+            y: vec![],
+            z: vec![],
+        }
+    }
+
+    /// Register `arrays`, given as `(array_id, particles)`, in place of the
+    /// arrays registered before. Panics if two arrays share an ID.
+    pub fn register_arrays(&mut self, arrays: &[(usize, &ParticleArray)]) {
+        self.ids.clear();
+        self.offsets = vec![0];
+        self.x.clear();
+        self.y.clear();
+        self.z.clear();
+        for &(id, particles) in arrays {
+            if self.ids.contains(&id) {
+                panic!("two particle arrays are registered with the id {}", id);
+            }
+            self.ids.push(id);
+            self.x.extend_from_slice(&particles.x);
+            self.y.extend_from_slice(&particles.y);
+            self.z.extend_from_slice(&particles.z);
+            self.offsets.push(self.x.len());
+        }
+
+        if self.grid.next.len() != self.x.len() {
+            self.grid.initialize_next(self.x.len());
+        }
+        self.grid
+            .register_particles_to_nnps(&self.x, &self.y, &self.z);
+    }
+
+    /// Range of the indices in `grid` of the particles of the array `id`.
+    fn range(&self, id: usize) -> std::ops::Range<usize> {
+        match self.ids.iter().position(|&other| other == id) {
+            Some(k) => self.offsets[k]..self.offsets[k + 1],
+            None => panic!("no particle array is registered with the id {}", id),
+        }
+    }
+
+    /// Number of particles of the array `id`.
+    pub fn no_of_particles(&self, id: usize) -> usize {
+        self.range(id).len()
+    }
+
+    /// Call `f` with the array ID, the index and the distance of every
+    /// particle of the array `source` within a distance `h` of particle `i`
+    /// of the array `destination`, or of every registered particle if
+    /// `source` is `None`. The particle itself is included. `h` must not be
+    /// larger than the cell size of `grid`.
+    pub fn for_each_neighbour_within<F: FnMut(usize, usize, f64)>(
+        &self,
+        destination: usize,
+        i: usize,
+        source: Option<usize>,
+        h: f64,
+        mut f: F,
+    ) {
+        let destination = self.range(destination);
+        if i >= destination.len() {
+            panic!(
+                "particle {} is out of an array of {} particles",
+                i,
+                destination.len()
+            );
+        }
+        let sources = source.map(|id| (id, self.range(id)));
+        let (x, y, z) = (&self.x, &self.y, &self.z);
+
+        self.grid
+            .for_each_neighbour_within(destination.start + i, x, y, z, h, |j, r| {
+                if let Some((id, sources)) = &sources {
+                    if sources.contains(&j) {
+                        f(*id, j - sources.start, r);
+                    }
+                } else {
+                    // the array holding `j`, the last one starting at or
+                    // before it
+                    let k = self.offsets.partition_point(|&offset| offset <= j) - 1;
+                    f(self.ids[k], j - self.offsets[k], r);
+                }
+            });
+    }
+
+    /// Neighbours within `h` of particle `i` of the array `destination`
+    /// among the array `source`, as `(array_id, index)` pairs, see
+    /// `for_each_neighbour_within`. The query particle itself is left out
+    /// when `exclude_self` is set.
+    pub fn get_neighbours_within(
+        &self,
+        destination: usize,
+        i: usize,
+        source: Option<usize>,
+        h: f64,
+        exclude_self: bool,
+    ) -> Vec<(usize, usize)> {
+        let mut neighbours = vec![];
+        self.for_each_neighbour_within(destination, i, source, h, |id, j, _| {
+            if !(exclude_self && id == destination && j == i) {
+                neighbours.push((id, j));
+            }
+        });
+        neighbours
+    }
+}
@@ -0,0 +1,54 @@
+use crate::aabb::Aabb;
+use crate::cell_grid::CellGrid;
+use crate::error::NnpsError;
+use crate::particle_index::ParticleIndex;
+use crate::real::Real;
+
+/// Cell list along the `x` axis, for one dimensional cases such as shock
+/// tubes, see `CellGrid`. The `y` and `z` coordinates passed to the `NNPS`
+/// methods are ignored.
+pub type NBS1D = CellGrid<1>;
+
+impl<T: Real, I: ParticleIndex> CellGrid<1, T, I> {
+    pub fn new(x_min: f64, x_max: f64, cell_size: f64) -> CellGrid<1, T, I> {
+        Self::from_aabb(Aabb::new([x_min], [x_max]), cell_size)
+    }
+
+    /// Same as `new`, returning an error instead of panicking when the limits
+    /// or the cell size are invalid.
+    pub fn try_new(x_min: f64, x_max: f64, cell_size: f64) -> Result<CellGrid<1, T, I>, NnpsError> {
+        Self::try_from_aabb(Aabb::new([x_min], [x_max]), cell_size)
+    }
+
+    pub fn from_limits_and_no_of_particles(
+        x_min: f64,
+        x_max: f64,
+        cell_size: f64,
+        no_of_particles: usize,
+    ) -> CellGrid<1, T, I> {
+        let mut nbs1d = Self::new(x_min, x_max, cell_size);
+        nbs1d.initialize_next(no_of_particles);
+        nbs1d
+    }
+
+    /// Make the grid periodic along `x`, see `set_periodic_axes`.
+    pub fn set_periodic(&mut self, x: bool) {
+        self.set_periodic_axes([x]);
+    }
+
+    /// Same as `set_periodic`, returning an error instead of panicking when
+    /// the axis has less than 3 cells.
+    pub fn try_set_periodic(&mut self, x: bool) -> Result<(), NnpsError> {
+        self.try_set_periodic_axes([x])
+    }
+
+    /// Same as `pairs`, keeping only the pairs closer than `h`, where `x`
+    /// are the coordinates the particles were registered with.
+    pub fn pairs_within<'a>(
+        &'a self,
+        x: &'a [T],
+        h: T,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.pairs_within_coords([x], h)
+    }
+}
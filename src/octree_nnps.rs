@@ -1,26 +1,251 @@
-#[derive(Debug)]
-pub struct OctTree{
-    pub val: usize,
-    pub l: Option<Box<Node>>,
-    pub r: Option<Box<Node>>,
+use crate::aabb::Aabb;
+use crate::real::Real;
+use crate::NNPS;
+
+/// Depth at which the leaves stop splitting, whatever their number of
+/// particles, so that coincident particles do not split forever.
+const MAX_DEPTH: usize = 32;
+
+/// A node of an `OctTree`. Leaves hold the indices of their particles, while
+/// inner nodes hold 4 (quadtree) or 8 (octree) children splitting their box
+/// at its centre.
+#[derive(Debug, Clone)]
+pub struct OctNode {
+    pub bounds: Aabb<3>,
+    pub particles: Vec<usize>,
+    pub children: Vec<OctNode>,
 }
 
-impl Node {
-    pub fn insert(&mut self, new_val: usize){
-        if self.val == new_val {
-            return
+impl OctNode {
+    fn new(bounds: Aabb<3>) -> OctNode {
+        OctNode {
+            bounds,
+            particles: vec![],
+            children: vec![],
         }
+    }
 
-        let target_node = if new_val < self.val { &mut self.l } else { &mut self.r };
+    /// Build the subtree of the box `bounds` holding `particles`.
+    fn build<T: Real>(
+        bounds: Aabb<3>,
+        particles: Vec<usize>,
+        tree: &OctTree<T>,
+        depth: usize,
+    ) -> OctNode {
+        let mut node = OctNode::new(bounds);
+        if particles.len() <= tree.leaf_capacity || depth >= MAX_DEPTH {
+            node.particles = particles;
+            return node;
+        }
 
-        match target_node {
-            &mut Some(ref mut subnode) => subnode.insert(new_val),
-            &mut None => {
-                let new_node = Node { val: new_val, l: None, r: None};
-                let boxed_node = Some(Box::new(new_node));
-                *target_node = boxed_node;
+        // split the box at its centre, bit `d` of the child index tells if
+        // the child is in the upper half along axis `d`
+        let no_children = 1 << tree.dim;
+        let (min, max) = (bounds.min, bounds.max);
+        let centre = [
+            0.5 * (min[0] + max[0]),
+            0.5 * (min[1] + max[1]),
+            0.5 * (min[2] + max[2]),
+        ];
+        let mut child_particles = vec![vec![]; no_children];
+        for i in particles {
+            let point = tree.position(i);
+            let mut child = 0;
+            for d in 0..tree.dim {
+                if point[d] >= centre[d] {
+                    child |= 1 << d;
+                }
             }
+            child_particles[child].push(i);
         }
 
+        for (child, particles) in child_particles.into_iter().enumerate() {
+            let mut child_min = min;
+            let mut child_max = max;
+            for d in 0..tree.dim {
+                if child & (1 << d) == 0 {
+                    child_max[d] = centre[d];
+                } else {
+                    child_min[d] = centre[d];
+                }
+            }
+            node.children.push(OctNode::build(
+                Aabb::new(child_min, child_max),
+                particles,
+                tree,
+                depth + 1,
+            ));
+        }
+        node
+    }
+}
+
+/// Adaptive tree for neighbour searches, a quadtree in 2D and an octree in
+/// 3D. Leaves are split once they hold more than `leaf_capacity` particles,
+/// so the memory follows the particles instead of the domain, which suits
+/// strongly clustered distributions.
+///
+/// Unlike the cell lists, `get_neighbours` only returns the particles within
+/// `radius` of the query point.
+///
+/// The positions are stored with the precision `T` of the coordinates, while
+/// the boxes of the nodes are in `f64`.
+#[derive(Debug, Clone)]
+pub struct OctTree<T = f64> {
+    pub root: Option<OctNode>,
+    /// Positions of the registered particles, `z` is zero in 2D.
+    pub points: Vec<[T; 3]>,
+    pub dim: usize,
+    pub leaf_capacity: usize,
+    pub radius: T,
+}
+
+impl<T: Real> OctTree<T> {
+    /// A quadtree for `dim = 2` or an octree for `dim = 3`, answering
+    /// `get_neighbours` with the particles within `radius`.
+    pub fn new(dim: usize, radius: T, leaf_capacity: usize) -> OctTree<T> {
+        if dim != 2 && dim != 3 {
+            panic!("an octree can only be built in 2 or 3 dimensions");
+        }
+        if leaf_capacity == 0 {
+            panic!("the leaves of an octree need room for at least one particle");
+        }
+        OctTree {
+            root: None,
+            points: vec![],
+            dim,
+            leaf_capacity,
+            radius,
+        }
+    }
+
+    /// Indices of the registered particles within `radius` of the point
+    /// `(x, y, z)`.
+    pub fn get_neighbours_in_radius(&self, x: T, y: T, z: T, radius: T) -> Vec<usize> {
+        WithinRadius::new(self, [x, y, z].map(T::to_f64), radius.to_f64()).collect()
+    }
+
+    /// Position of the registered particle `i` in `f64`.
+    fn position(&self, i: usize) -> [f64; 3] {
+        self.points[i].map(T::to_f64)
+    }
+
+    /// Depth of the deepest leaf, the root alone has a depth of zero.
+    pub fn depth(&self) -> usize {
+        fn depth(node: &OctNode) -> usize {
+            node.children
+                .iter()
+                .map(|child| 1 + depth(child))
+                .max()
+                .unwrap_or(0)
+        }
+        self.root.as_ref().map_or(0, depth)
+    }
+}
+
+/// Lazy walk over the particles within a radius of a point. Nodes whose box
+/// is farther than the radius are skipped along with all their particles.
+struct WithinRadius<'a, T> {
+    tree: &'a OctTree<T>,
+    point: [f64; 3],
+    radius_squared: f64,
+    // nodes left to visit
+    stack: Vec<&'a OctNode>,
+    // particles of the current leaf left to check
+    leaf: &'a [usize],
+}
+
+impl<'a, T: Real> WithinRadius<'a, T> {
+    fn new(tree: &'a OctTree<T>, point: [f64; 3], radius: f64) -> Self {
+        let [x, y, z] = point;
+        let z = if tree.dim == 2 { 0. } else { z };
+        WithinRadius {
+            tree,
+            point: [x, y, z],
+            radius_squared: radius * radius,
+            stack: tree.root.iter().collect(),
+            leaf: &[],
+        }
+    }
+}
+
+impl<'a, T: Real> Iterator for WithinRadius<'a, T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some((&i, rest)) = self.leaf.split_first() {
+                self.leaf = rest;
+                let p = self.tree.position(i);
+                let dx = self.point[0] - p[0];
+                let dy = self.point[1] - p[1];
+                let dz = self.point[2] - p[2];
+                if dx * dx + dy * dy + dz * dz <= self.radius_squared {
+                    return Some(i);
+                }
+            }
+
+            let node = self.stack.pop()?;
+            if node.bounds.distance_squared(self.point) > self.radius_squared {
+                continue;
+            }
+            if node.children.is_empty() {
+                self.leaf = &node.particles;
+            } else {
+                self.stack.extend(node.children.iter().rev());
+            }
+        }
+    }
+}
+
+impl<T: Real> NNPS<T> for OctTree<T> {
+    fn register_particles_to_nnps(&mut self, x: &[T], y: &[T], z: &[T]) {
+        let dim = self.dim;
+        let zero = T::from_f64(0.);
+        self.points = (0..x.len())
+            .map(|i| [x[i], y[i], if dim == 2 { zero } else { z[i] }])
+            .collect();
+
+        // particles with a non finite position can not be placed in a box,
+        // leave them out
+        let particles: Vec<usize> = (0..x.len())
+            .filter(|&i| self.points[i].iter().all(|v| v.is_finite()))
+            .collect();
+
+        // the root covers the bounding box of the particles
+        let bounds = Aabb::from_points(particles.iter().map(|&i| self.position(i)))
+            .unwrap_or(Aabb::new([0.; 3], [0.; 3]));
+
+        self.root = Some(OctNode::build(bounds, particles, self, 0));
+    }
+
+    fn neighbours_iter(&self, x: T, y: T, z: T) -> impl Iterator<Item = usize> + '_ {
+        WithinRadius::new(self, [x, y, z].map(T::to_f64), self.radius.to_f64())
+    }
+
+    fn get_neighbours_into(&self, x: T, y: T, z: T, neighbours: &mut Vec<usize>) {
+        neighbours.clear();
+        neighbours.extend(self.neighbours_iter(x, y, z));
+    }
+
+    fn for_each_neighbour_within<F: FnMut(usize, T)>(
+        &self,
+        i: usize,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        h: T,
+        mut f: F,
+    ) {
+        // the tree has no fixed search range, any `h` can be used
+        let zi = if self.dim == 2 { 0. } else { z[i].to_f64() };
+        let point = [x[i].to_f64(), y[i].to_f64(), zi];
+        for j in WithinRadius::new(self, point, h.to_f64()) {
+            let p = self.position(j);
+            let dx = point[0] - p[0];
+            let dy = point[1] - p[1];
+            let dz = point[2] - p[2];
+            f(j, T::from_f64((dx * dx + dy * dy + dz * dz).sqrt()));
+        }
     }
 }
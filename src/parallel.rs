@@ -0,0 +1,21 @@
+use rayon::prelude::*;
+
+use crate::real::Real;
+use crate::NNPS;
+
+/// Neighbours within `h` of every registered particle, one list per particle,
+/// built in parallel. The queries only borrow `nnps`, so any backend which
+/// can be shared between threads works.
+pub fn par_neighbour_lists<T: Real, N: NNPS<T> + Sync>(
+    nnps: &N,
+    x: &[T],
+    y: &[T],
+    z: &[T],
+    h: T,
+    exclude_self: bool,
+) -> Vec<Vec<usize>> {
+    (0..x.len())
+        .into_par_iter()
+        .map(|i| nnps.get_neighbours_within(i, x, y, z, h, exclude_self))
+        .collect()
+}
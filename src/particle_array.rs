@@ -1,3 +1,5 @@
+use crate::aabb::Aabb;
+
 pub struct ParticleArray {
     pub x: Vec<f64>,
     pub y: Vec<f64>,
@@ -24,6 +26,41 @@ impl ParticleArray {
 
         return particles;
     }
+
+    /// Bounding box of the particles with a finite position, `None` if there
+    /// are none.
+    pub fn bounding_box(&self) -> Option<Aabb<3>> {
+        Aabb::from_points((0..self.x.len()).map(|i| [self.x[i], self.y[i], self.z[i]]))
+    }
+
+    /// Move the particle at `order[k]` to position `k`, and return the
+    /// inverse mapping: the particle at `i` before the call is at
+    /// `inverse[i]` after it. `order` has to be a permutation of the
+    /// particles.
+    pub fn permute(&mut self, order: &[usize]) -> Vec<usize> {
+        if order.len() != self.x.len() {
+            panic!("the permutation does not cover all the particles");
+        }
+        let mut inverse = vec![usize::MAX; order.len()];
+        for (k, &i) in order.iter().enumerate() {
+            inverse[i] = k;
+        }
+        if inverse.contains(&usize::MAX) {
+            panic!("the order is not a permutation of the particles");
+        }
+
+        for values in [&mut self.x, &mut self.y, &mut self.z, &mut self.radius] {
+            *values = order.iter().map(|&i| values[i]).collect();
+        }
+        inverse
+    }
+
+    /// Indices of the particles inside `region`.
+    pub fn indices_in(&self, region: &Aabb<3>) -> Vec<usize> {
+        (0..self.x.len())
+            .filter(|&i| region.contains([self.x[i], self.y[i], self.z[i]]))
+            .collect()
+    }
 }
 
 #[macro_export]
@@ -0,0 +1,45 @@
+use std::fmt::Debug;
+
+/// Integer type of the particle indices stored in the `head` and `next`
+/// arrays of the cell lists, `usize` or `u32`. With `u32` the arrays take
+/// half the memory, which matters for fine 3D grids where most cells are
+/// empty, at the cost of at most `u32::MAX` particles, indexed from 0 to
+/// `u32::MAX - 1`, `u32::MAX` itself being the sentinel.
+pub trait ParticleIndex: Copy + Debug + PartialEq + Send + Sync + 'static {
+    /// Marks an empty cell in `head` and the end of a list in `next`.
+    const NONE: Self;
+
+    /// Number of particles which can be indexed, the sentinel being left out.
+    const MAX_PARTICLES: usize;
+
+    /// The index `i`, which has to be below `MAX_PARTICLES`.
+    fn from_usize(i: usize) -> Self;
+
+    fn to_usize(self) -> usize;
+}
+
+impl ParticleIndex for usize {
+    const NONE: usize = usize::MAX;
+    const MAX_PARTICLES: usize = usize::MAX;
+
+    fn from_usize(i: usize) -> usize {
+        i
+    }
+
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+impl ParticleIndex for u32 {
+    const NONE: u32 = u32::MAX;
+    const MAX_PARTICLES: usize = u32::MAX as usize;
+
+    fn from_usize(i: usize) -> u32 {
+        i as u32
+    }
+
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
@@ -0,0 +1,25 @@
+/// Map `value` back into `[min, max)` when the axis is periodic. Values on
+/// non periodic axes are returned untouched.
+pub(crate) fn wrap_coordinate(value: f64, min: f64, max: f64, periodic: bool) -> f64 {
+    if periodic {
+        min + (value - min).rem_euclid(max - min)
+    } else {
+        value
+    }
+}
+
+/// Cell coordinate of `value` along one axis, for a value in the domain. The
+/// edge index is clamped, so the values on the upper limit, or in the part of
+/// the domain left over by a truncated cell count, fall in the last cell
+/// instead of spilling into the next row.
+pub(crate) fn cell_coordinate(value: f64, min: f64, cell_size: f64, no_cells: usize) -> usize {
+    (((value - min) / cell_size) as usize).min(no_cells - 1)
+}
+
+/// Wrap a (possibly out of range) cell coordinate onto `0..no_cells`,
+/// returning the wrapped coordinate and the number of domain lengths the
+/// neighbour image has to be moved by.
+pub(crate) fn wrap_cell(cell: isize, no_cells: usize) -> (usize, isize) {
+    let n = no_cells as isize;
+    (cell.rem_euclid(n) as usize, cell.div_euclid(n))
+}
@@ -1,3 +1,21 @@
 pub use crate::NNPS;
+pub use crate::nbs1d::NBS1D;
 pub use crate::nbs2d::NBS2D;
 pub use crate::nbs3d::NBS3D;
+pub use crate::octree_nnps::OctTree;
+pub use crate::kdtree::KdTree;
+pub use crate::hash_grid::HashGrid;
+pub use crate::auto_fit::AutoFit;
+pub use crate::registration::RegistrationReport;
+pub use crate::NnpsError;
+pub use crate::builder::NbsBuilder;
+pub use crate::aabb::Aabb;
+pub use crate::cell_count::CellCountPolicy;
+pub use crate::cell_grid::CellGrid;
+pub use crate::real::Real;
+pub use crate::particle_index::ParticleIndex;
+pub use crate::verlet::VerletList;
+pub use crate::reorder::SortKey;
+pub use crate::sorted_grid::SortedCellGrid;
+pub use crate::cell_ordering::CellOrdering;
+pub use crate::multi_array::MultiArrayGrid;
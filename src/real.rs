@@ -0,0 +1,57 @@
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Floating point type of the particle coordinates, `f32` or `f64`. The
+/// `NNPS` backends read the coordinates through it, so simulations storing
+/// their positions in `f32` can pass their arrays without copying them.
+///
+/// The cell lists keep their own geometry (domain, cell sizes and periodic
+/// shifts) in `f64`, and convert the coordinates as they read them.
+pub trait Real:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    fn is_finite(self) -> bool;
+
+    fn sqrt(self) -> Self;
+}
+
+macro_rules! impl_real {
+    ($($t:ty),*) => {
+        $(
+            impl Real for $t {
+                fn from_f64(value: f64) -> Self {
+                    value as $t
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn is_finite(self) -> bool {
+                    <$t>::is_finite(self)
+                }
+
+                fn sqrt(self) -> Self {
+                    <$t>::sqrt(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_real!(f32, f64);
@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::error::NnpsError;
+
+/// Particles a cell list could not register, as returned by
+/// `CellGrid::register_particles_checked`. These particles are in no cell,
+/// so they neither find nor are found as neighbours.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegistrationReport {
+    /// Indices of the particles with a finite position outside the domain.
+    pub out_of_domain: Vec<usize>,
+    /// Indices of the particles with a NaN or infinite coordinate.
+    pub non_finite: Vec<usize>,
+}
+
+impl RegistrationReport {
+    /// Number of particles left out of the grid.
+    pub fn no_of_lost_particles(&self) -> usize {
+        self.out_of_domain.len() + self.non_finite.len()
+    }
+
+    /// `true` when every particle was registered.
+    pub fn is_clean(&self) -> bool {
+        self.no_of_lost_particles() == 0
+    }
+}
+
+/// Error returned by `CellGrid::register_particles_checked`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistrationError {
+    /// Some particles could not be registered in strict mode. The grid is
+    /// still filled with the other particles.
+    LostParticles { report: RegistrationReport },
+    /// The particles can not be registered at all, for instance because
+    /// there are more than the index type can address.
+    Nnps(NnpsError),
+}
+
+impl From<NnpsError> for RegistrationError {
+    fn from(error: NnpsError) -> Self {
+        RegistrationError::Nnps(error)
+    }
+}
+
+impl fmt::Display for RegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistrationError::LostParticles { report } => write!(
+                f,
+                "{} particles out of the domain and {} particles with a non finite position",
+                report.out_of_domain.len(),
+                report.non_finite.len()
+            ),
+            RegistrationError::Nnps(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for RegistrationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RegistrationError::LostParticles { .. } => None,
+            RegistrationError::Nnps(error) => Some(error),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::cell_grid::{particle_position, CellGrid};
+use crate::morton;
+use crate::particle_array::ParticleArray;
+use crate::particle_index::ParticleIndex;
+use crate::real::Real;
+
+/// Key the particles are sorted on by `CellGrid::sorting_permutation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    /// Index of the cell in `head`, so the particles follow the cell
+    /// ordering of the grid, see `CellOrdering`.
+    #[default]
+    CellIndex,
+    /// Morton key of the cell coordinates, see `morton`, which keeps
+    /// neighbouring rows and planes close in memory whatever the cell
+    /// ordering.
+    Morton,
+}
+
+impl<const D: usize, T: Real, I: ParticleIndex> CellGrid<D, T, I> {
+    /// Permutation sorting the particles at `x`, `y` and `z` on the key of
+    /// their cell: `order[k]` is the particle moved to position `k`. The
+    /// particles of a cell keep their relative order, and the particles out
+    /// of the domain go last.
+    pub fn sorting_permutation(&self, x: &[T], y: &[T], z: &[T], key: SortKey) -> Vec<usize> {
+        let coords = [x, y, z];
+        let keys: Vec<u64> = (0..x.len())
+            .map(|i| match self.cell_of(particle_position(&coords, i)) {
+                Some(cell) => match key {
+                    SortKey::CellIndex => self.flat_index(cell) as u64,
+                    SortKey::Morton => morton::encode(cell),
+                },
+                None => u64::MAX,
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..x.len()).collect();
+        order.sort_by_key(|&i| keys[i]);
+        order
+    }
+}
+
+impl<const D: usize, I: ParticleIndex> CellGrid<D, f64, I> {
+    /// Sort `particles` on the key of their cell, so that the particles of a
+    /// cell sit next to each other in memory, and return the inverse mapping:
+    /// the particle at `i` before the call is at `inverse[i]` after it. The
+    /// particles have to be registered again afterwards.
+    pub fn reorder(&self, particles: &mut ParticleArray, key: SortKey) -> Vec<usize> {
+        let order = self.sorting_permutation(&particles.x, &particles.y, &particles.z, key);
+        particles.permute(&order)
+    }
+}
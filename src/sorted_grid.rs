@@ -0,0 +1,168 @@
+use crate::cell_grid::{
+    check_no_of_particles, first_coordinates, lost_particles_report, particle_position,
+    stencil_len, stencil_offset, CellGrid,
+};
+use crate::particle_index::ParticleIndex;
+use crate::real::Real;
+use crate::NNPS;
+
+/// Cell list storing the particles sorted by cell instead of in linked
+/// lists. The registration is a counting sort: the particles of every cell
+/// are counted in `cell_count`, a prefix sum of the counts gives the start
+/// of every cell in `cell_start`, and the particles are scattered into
+/// `particles`, so the particles of cell `c` are the contiguous slice
+/// `particles[cell_start[c]..cell_start[c] + cell_count[c]]`, in increasing
+/// index order. Particles out of the domain are left out.
+///
+/// The cells, the domain, the periodic axes, auto fit and the strict mode
+/// are taken from `grid`, whose own `head` and `next` lists are not used.
+#[derive(Debug, Clone)]
+pub struct SortedCellGrid<const D: usize, T = f64, I = usize> {
+    pub grid: CellGrid<D, T, I>,
+    /// Position in `particles` of the first particle of every cell.
+    pub cell_start: Vec<I>,
+    /// Number of particles in every cell.
+    pub cell_count: Vec<I>,
+    /// Indices of the registered particles, sorted by cell.
+    pub particles: Vec<I>,
+}
+
+impl<const D: usize, T: Real, I: ParticleIndex> SortedCellGrid<D, T, I> {
+    /// Sorted layout over the cells of `grid`. Nothing is registered until
+    /// `register_particles_to_nnps`.
+    pub fn new(grid: CellGrid<D, T, I>) -> SortedCellGrid<D, T, I> {
+        let no_of_indices = grid.head.len();
+        SortedCellGrid {
+            grid,
+            cell_start: vec![I::from_usize(0); no_of_indices],
+            cell_count: vec![I::from_usize(0); no_of_indices],
+            particles: vec![],
+        }
+    }
+
+    /// Indices of the particles of the cell `idx`, numbered as in
+    /// `CellGrid::head`.
+    pub fn cell_particles(&self, idx: usize) -> &[I] {
+        let start = self.cell_start[idx].to_usize();
+        &self.particles[start..start + self.cell_count[idx].to_usize()]
+    }
+
+    /// Particles of the cells around `point`, each with the periodic shift of
+    /// its cell, see `CellGrid::get_neighbours_with_shifts`.
+    fn neighbours(&self, point: [f64; D]) -> impl Iterator<Item = (usize, [f64; D])> + '_ {
+        let grid = &self.grid;
+        let home = grid.cell_of(point).map(|cell| cell.map(|c| c as isize));
+        home.into_iter()
+            .flat_map(move |home| {
+                (0..stencil_len(D))
+                    .filter_map(move |k| grid.neighbour_cell(home, stencil_offset(k)))
+            })
+            .flat_map(move |(idx, shift)| {
+                self.cell_particles(idx)
+                    .iter()
+                    .map(move |&j| (j.to_usize(), shift))
+            })
+    }
+
+    /// Same as `CellGrid::get_neighbours_with_shifts`.
+    pub fn get_neighbours_with_shifts(&self, x: T, y: T, z: T) -> Vec<(usize, [f64; D])> {
+        self.neighbours(first_coordinates(x, y, z)).collect()
+    }
+}
+
+impl<const D: usize, T: Real, I: ParticleIndex> NNPS<T> for SortedCellGrid<D, T, I> {
+    fn register_particles_to_nnps(&mut self, x: &[T], y: &[T], z: &[T]) {
+        check_no_of_particles::<I>(x.len());
+        let coords = [x, y, z];
+        self.grid
+            .fit_domain(&coords, x.len())
+            .unwrap_or_else(|e| panic!("{}", e));
+        let no_of_indices = self.grid.head.len();
+
+        // cell of every particle, the particles out of domain being marked
+        // with the number of cell indices
+        let grid = &self.grid;
+        let cells: Vec<usize> = (0..x.len())
+            .map(|i| {
+                grid.cell_of(particle_position(&coords, i))
+                    .map_or(no_of_indices, |cell| grid.flat_index(cell))
+            })
+            .collect();
+
+        let mut counts = vec![0; no_of_indices];
+        for &idx in cells.iter().filter(|&&idx| idx < no_of_indices) {
+            counts[idx] += 1;
+        }
+        // exclusive prefix sum of the counts
+        let mut starts = Vec::with_capacity(no_of_indices);
+        let mut no_registered = 0;
+        for &count in &counts {
+            starts.push(no_registered);
+            no_registered += count;
+        }
+
+        // scatter the particles, the cursor of every cell moving from its
+        // start to its end
+        let mut cursor = starts.clone();
+        self.particles = vec![I::NONE; no_registered];
+        for (i, &idx) in cells.iter().enumerate() {
+            if idx < no_of_indices {
+                self.particles[cursor[idx]] = I::from_usize(i);
+                cursor[idx] += 1;
+            }
+        }
+
+        self.cell_start = starts.into_iter().map(I::from_usize).collect();
+        self.cell_count = counts.into_iter().map(I::from_usize).collect();
+
+        // in strict mode a lost particle is an error, as with the linked
+        // lists
+        if self.grid.strict {
+            let lost = (0..x.len()).filter(|&i| cells[i] == no_of_indices);
+            let report = lost_particles_report::<T, D>(&coords, lost);
+            if let Err(error) = self.grid.check_strict(report) {
+                panic!("{}", error);
+            }
+        }
+    }
+
+    fn periods(&self) -> [Option<f64>; 3] {
+        self.grid.periods()
+    }
+
+    fn neighbours_iter(&self, x: T, y: T, z: T) -> impl Iterator<Item = usize> + '_ {
+        self.neighbours(first_coordinates(x, y, z)).map(|(i, _)| i)
+    }
+
+    fn get_neighbours_into(&self, x: T, y: T, z: T, neighbours: &mut Vec<usize>) {
+        neighbours.clear();
+        neighbours.extend(self.neighbours_iter(x, y, z));
+    }
+
+    fn for_each_neighbour_within<F: FnMut(usize, T)>(
+        &self,
+        i: usize,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        h: T,
+        mut f: F,
+    ) {
+        let grid = &self.grid;
+        let coords = [x, y, z];
+        let h_squared = h.to_f64() * h.to_f64();
+        let pi = grid
+            .domain
+            .wrap(particle_position(&coords, i), grid.periodic);
+
+        for (j, shift) in self.neighbours(pi) {
+            let pj = grid
+                .domain
+                .wrap(particle_position(&coords, j), grid.periodic);
+            let r_squared: f64 = (0..D).map(|d| (pi[d] - pj[d] - shift[d]).powi(2)).sum();
+            if r_squared <= h_squared {
+                f(j, T::from_f64(r_squared.sqrt()));
+            }
+        }
+    }
+}
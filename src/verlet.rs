@@ -0,0 +1,172 @@
+use crate::csr::CsrNeighbourList;
+use crate::real::Real;
+use crate::NNPS;
+
+/// Verlet neighbour lists on top of a cell list, or any other `NNPS`
+/// backend. The lists hold the neighbours within `h + skin` of every
+/// particle, so they stay valid while no particle has moved by more than
+/// `skin / 2` since they were built: two particles coming closer than `h`
+/// were then closer than `h + skin` at the build. `update` keeps track of the
+/// largest displacement and only registers the particles and rebuilds the
+/// lists once it goes past `skin / 2`.
+///
+/// The search range of `nnps` (the cell size for the cell lists) has to be
+/// at least `h + skin`. Along the periodic axes of `nnps`, see
+/// `NNPS::periods`, the distances are measured to the minimum image.
+#[derive(Debug, Clone)]
+pub struct VerletList<N, T = f64> {
+    pub nnps: N,
+    /// Number of coordinates of the particles, the other slices are ignored.
+    pub dim: usize,
+    pub h: T,
+    pub skin: T,
+    /// Neighbours within `h + skin` of every particle at the last build, the
+    /// particle itself left out.
+    pub lists: CsrNeighbourList<T>,
+    /// Positions of the particles at the last build.
+    pub reference: Vec<[f64; 3]>,
+    /// Largest displacement since the last build, as of the last `update`.
+    pub max_displacement: f64,
+    /// Number of calls to `update`.
+    pub no_of_updates: usize,
+    /// Number of times the cell lists and the Verlet lists were rebuilt.
+    pub no_of_builds: usize,
+}
+
+/// Squared distance between the points `a` and `b`, taking the minimum image
+/// along the axes with a period.
+fn distance_squared(periods: &[Option<f64>; 3], a: [f64; 3], b: [f64; 3]) -> f64 {
+    (0..3)
+        .map(|d| {
+            let mut delta = a[d] - b[d];
+            if let Some(period) = periods[d] {
+                delta -= period * (delta / period).round();
+            }
+            delta * delta
+        })
+        .sum()
+}
+
+impl<N: NNPS<T>, T: Real> VerletList<N, T> {
+    /// Verlet lists for particles with `dim = 1`, `2` or `3` coordinates,
+    /// searched through `nnps`. Nothing is built until the first `update`.
+    pub fn new(nnps: N, dim: usize, h: T, skin: T) -> VerletList<N, T> {
+        if dim == 0 || dim > 3 {
+            panic!("verlet lists can only be built in 1, 2 or 3 dimensions");
+        }
+        if !skin.is_finite() || skin < T::from_f64(0.) {
+            panic!("the skin of the verlet lists can not be negative");
+        }
+        VerletList {
+            nnps,
+            dim,
+            h,
+            skin,
+            lists: CsrNeighbourList {
+                offsets: vec![0],
+                indices: vec![],
+                distances: None,
+            },
+            reference: vec![],
+            max_displacement: 0.,
+            no_of_updates: 0,
+            no_of_builds: 0,
+        }
+    }
+
+    /// Position of particle `i` in `f64`, the coordinates past `dim` being
+    /// zero.
+    fn position(&self, x: &[T], y: &[T], z: &[T], i: usize) -> [f64; 3] {
+        let mut position = [0.; 3];
+        for (d, coords) in [x, y, z].iter().take(self.dim).enumerate() {
+            position[d] = coords[i].to_f64();
+        }
+        position
+    }
+
+    /// Bring the lists up to date with the particles at `x`, `y` and `z`. The
+    /// lists are rebuilt on the first call, when the number of particles
+    /// changes, and when a particle has moved by more than `skin / 2` since
+    /// the last build. Returns whether the lists were rebuilt.
+    pub fn update(&mut self, x: &[T], y: &[T], z: &[T]) -> bool {
+        self.no_of_updates += 1;
+
+        let mut max_squared: f64 = 0.;
+        if self.reference.len() == x.len() {
+            // a particle wrapped through a periodic face has not moved by a
+            // whole period
+            let periods = self.nnps.periods();
+            for (i, reference) in self.reference.iter().enumerate() {
+                let position = self.position(x, y, z, i);
+                let squared = distance_squared(&periods, position, *reference);
+                // a NaN position forces a rebuild
+                if squared.is_nan() || squared > max_squared {
+                    max_squared = squared;
+                }
+            }
+            self.max_displacement = max_squared.sqrt();
+            if self.max_displacement <= 0.5 * self.skin.to_f64() {
+                return false;
+            }
+        }
+
+        self.rebuild(x, y, z);
+        true
+    }
+
+    /// Register the particles and rebuild the lists, whatever the particles
+    /// moved.
+    pub fn rebuild(&mut self, x: &[T], y: &[T], z: &[T]) {
+        self.nnps.register_particles_to_nnps(x, y, z);
+        let range = self.h + self.skin;
+        self.lists = self.nnps.get_neighbours_csr(x, y, z, range, true, false);
+        self.reference = (0..x.len()).map(|i| self.position(x, y, z, i)).collect();
+        self.max_displacement = 0.;
+        self.no_of_builds += 1;
+    }
+
+    /// Neighbours within `h + skin` of particle `i` at the last build, a
+    /// superset of its current neighbours within `h`.
+    pub fn candidates(&self, i: usize) -> &[usize] {
+        self.lists.neighbours(i)
+    }
+
+    /// Call `f` with the index and the distance of every particle within `h`
+    /// of particle `i`, the particle itself left out, picked among its
+    /// candidates.
+    pub fn for_each_neighbour<F: FnMut(usize, T)>(
+        &self,
+        i: usize,
+        x: &[T],
+        y: &[T],
+        z: &[T],
+        mut f: F,
+    ) {
+        let h = self.h.to_f64();
+        let periods = self.nnps.periods();
+        let pi = self.position(x, y, z, i);
+        for &j in self.candidates(i) {
+            let pj = self.position(x, y, z, j);
+            let r_squared = distance_squared(&periods, pi, pj);
+            if r_squared <= h * h {
+                f(j, T::from_f64(r_squared.sqrt()));
+            }
+        }
+    }
+
+    /// Indices of the particles within `h` of particle `i`, see
+    /// `for_each_neighbour`.
+    pub fn get_neighbours(&self, i: usize, x: &[T], y: &[T], z: &[T]) -> Vec<usize> {
+        let mut neighbours = vec![];
+        self.for_each_neighbour(i, x, y, z, |j, _| neighbours.push(j));
+        neighbours
+    }
+
+    /// Fraction of the updates which had to rebuild the lists.
+    pub fn rebuild_ratio(&self) -> f64 {
+        if self.no_of_updates == 0 {
+            return 0.;
+        }
+        self.no_of_builds as f64 / self.no_of_updates as f64
+    }
+}
@@ -0,0 +1,37 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+/// Reproducible pseudo random states, one per particle, from a linear
+/// congruential generator seeded with `seed`.
+pub fn random_states(no_of_particles: usize, seed: u64) -> impl Iterator<Item = u64> {
+    let mut state = seed;
+    (0..no_of_particles).map(move |_| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        state
+    })
+}
+
+/// Scattered but reproducible points in `[0, length)`.
+pub fn scattered_in(no_of_particles: usize, length: f64, seed: u64) -> Vec<f64> {
+    random_states(no_of_particles, seed)
+        .map(|state| (state >> 11) as f64 / (1u64 << 53) as f64 * length)
+        .collect()
+}
+
+/// Scattered but reproducible points in `[0, 1)`.
+pub fn scattered(no_of_particles: usize, seed: u64) -> Vec<f64> {
+    scattered_in(no_of_particles, 1., seed)
+}
+
+/// Indices of the particles within `h` of `point`, by checking all of them.
+/// `coords` holds one coordinate slice per axis.
+pub fn brute_force<const D: usize>(coords: [&[f64]; D], point: [f64; D], h: f64) -> Vec<usize> {
+    (0..coords[0].len())
+        .filter(|&j| {
+            let r_squared: f64 = (0..D).map(|d| (coords[d][j] - point[d]).powi(2)).sum();
+            r_squared <= h * h
+        })
+        .collect()
+}
@@ -0,0 +1,78 @@
+extern crate neighbours;
+
+// local library imports
+use neighbours::aabb::Aabb;
+use neighbours::nbs2d::NBS2D;
+use neighbours::nbs3d::NBS3D;
+use neighbours::particle_array::ParticleArray;
+use neighbours::NNPS;
+
+#[test]
+fn test_aabb_set_operations() {
+    let a = Aabb::new([0., 0.], [2., 1.]);
+    let b = Aabb::new([1., -1.], [3., 0.5]);
+
+    assert!(a.contains([2., 0.]));
+    assert!(!a.contains([2.1, 0.]));
+    assert_eq!([2., 1.], a.lengths());
+
+    assert_eq!(Aabb::new([0., -1.], [3., 1.]), a.union(&b));
+    assert_eq!(Some(Aabb::new([1., 0.], [2., 0.5])), a.intersection(&b));
+    assert_eq!(None, a.intersection(&Aabb::new([5., 5.], [6., 6.])));
+
+    assert_eq!(Aabb::new([-0.5, -0.5], [2.5, 1.5]), a.padded(0.5));
+    assert_eq!(0., a.distance_squared([1., 0.5]));
+    assert_eq!(2., a.distance_squared([3., 2.]));
+}
+
+#[test]
+fn test_aabb_periodic_wrap_and_points() {
+    let domain = Aabb::new([0., 0., -1.], [2., 2., 1.]);
+    assert_eq!(
+        [0.5, 2.5, -0.5],
+        domain.wrap([2.5, 2.5, 1.5], [true, false, true])
+    );
+
+    let points = vec![[1., 2., 3.], [f64::NAN, 0., 0.], [-1., 5., 0.]];
+    assert_eq!(
+        Some(Aabb::new([-1., 2., 0.], [1., 5., 3.])),
+        Aabb::from_points(points)
+    );
+    assert_eq!(None, Aabb::<3>::from_points(vec![]));
+}
+
+#[test]
+fn test_aabb_builds_the_grids() {
+    let domain = Aabb::new([0., 0., 0.], [3., 2., 1.]);
+    let nbs3d = NBS3D::from_aabb(domain, 1.);
+    assert_eq!(6, nbs3d.total_no_cells);
+    assert_eq!(domain, nbs3d.domain);
+
+    // the limits constructor hands over the grid it builds
+    let mut nbs3d = NBS3D::from_limits_and_no_of_particles(0., 3., 0., 2., 0., 1., 1., 2);
+    assert_eq!(2, nbs3d.next.len());
+    nbs3d.register_particles_to_nnps(&[0.5, 2.5], &[0.5, 1.5], &[0.5, 0.5]);
+    assert_eq!(vec![0], nbs3d.get_neighbours(0.5, 0.5, 0.5));
+
+    let nbs2d = NBS2D::from_maximum_coordinate(2., 1.);
+    assert_eq!(Aabb::new([-2., -2.], [2., 2.]), nbs2d.domain);
+    assert!(NBS2D::try_from_aabb(Aabb::new([0., 0.], [0.5, 3.]), 1.).is_err());
+}
+
+#[test]
+fn test_particle_array_bounding_box_and_region_selection() {
+    let particles = ParticleArray::from_xyz_rad(
+        &[0., 1., 2., 3.],
+        &[0., -1., 1., 0.],
+        &[0., 0., 0., 5.],
+        &[0.1; 4],
+    );
+    assert_eq!(
+        Some(Aabb::new([0., -1., 0.], [3., 1., 5.])),
+        particles.bounding_box()
+    );
+
+    let region = Aabb::new([0.5, -2., -1.], [2.5, 2., 1.]);
+    assert_eq!(vec![1, 2], particles.indices_in(&region));
+    assert_eq!(None, ParticleArray::new(0).bounding_box());
+}
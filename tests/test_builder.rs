@@ -0,0 +1,135 @@
+extern crate neighbours;
+
+// local library imports
+use neighbours::aabb::Aabb;
+use neighbours::builder::NbsBuilder;
+use neighbours::cell_count::CellCountPolicy;
+use neighbours::nbs3d::NBS3D;
+use neighbours::{NnpsError, NNPS};
+
+#[test]
+fn test_builder_matches_positional_constructors() {
+    let nbs3d = NbsBuilder::new()
+        .x_limits(0., 3.)
+        .y_limits(-1., 2.)
+        .z_limits(0., 2.)
+        .cell_size(0.5)
+        .no_of_particles(10)
+        .capacity(50)
+        .build_3d()
+        .unwrap();
+    let mut expected = NBS3D::new(0., 3., -1., 2., 0., 2., 0.5);
+    expected.initialize_next(10);
+
+    assert_eq!(expected.head, nbs3d.head);
+    assert_eq!(expected.next, nbs3d.next);
+    assert!(nbs3d.next.capacity() >= 50);
+    assert_eq!(expected.no_cells, nbs3d.no_cells);
+
+    let nbs2d = NbsBuilder::new()
+        .max_coordinate(2.)
+        .cell_size(1.)
+        .build_2d()
+        .unwrap();
+    assert_eq!(Aabb::new([-2., -2.], [2., 2.]), nbs2d.domain);
+    assert_eq!(16, nbs2d.total_no_cells);
+}
+
+#[test]
+fn test_builder_sets_periodicity_and_modes() {
+    let mut nbs2d = NbsBuilder::new()
+        .x_limits(0., 3.)
+        .y_limits(0., 3.)
+        .cell_size(1.)
+        .periodic_y(true)
+        .strict(true)
+        .no_of_particles(2)
+        .build_2d()
+        .unwrap();
+    assert_eq!([false, true], nbs2d.periodic);
+
+    // the particle at the top finds the one at the bottom through the
+    // periodic face
+    let x = vec![1.5, 1.5];
+    let y = vec![0.1, 2.9];
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+    assert_eq!(
+        vec![0, 1],
+        nbs2d.get_neighbours_within(0, &x, &y, &[0.], 0.5, false)
+    );
+
+    // the strict mode is on
+    assert!(nbs2d
+        .register_particles_checked(&[5.], &[0.], &[0.])
+        .is_err());
+}
+
+#[test]
+fn test_builder_validates_the_parameters() {
+    let builder = NbsBuilder::new().x_limits(0., 3.).y_limits(0., 3.);
+    assert_eq!(
+        Err(NnpsError::MissingCellSize),
+        builder.build_2d().map(|_| ())
+    );
+
+    let builder = builder.cell_size(1.);
+    assert_eq!(
+        Err(NnpsError::MissingLimits { axis: 2 }),
+        builder.build_3d().map(|_| ())
+    );
+
+    let builder = builder.z_limits(0., 2.).periodic_z(true);
+    assert_eq!(
+        Err(NnpsError::TooFewPeriodicCells { axis: 2 }),
+        builder.build_3d().map(|_| ())
+    );
+    // z plays no part in 2D
+    assert!(builder.build_2d().is_ok());
+
+    assert_eq!(
+        Err(NnpsError::InvertedBounds {
+            axis: 0,
+            min: 3.,
+            max: 0.
+        }),
+        builder.x_limits(3., 0.).build_2d().map(|_| ())
+    );
+}
+
+#[test]
+fn test_builder_with_a_cell_size_per_axis() {
+    let nbs3d = NbsBuilder::new()
+        .x_limits(0., 10.)
+        .y_limits(0., 1.)
+        .z_limits(0., 1.)
+        .cell_sizes(2., 0.5, 0.25)
+        .build_3d()
+        .unwrap();
+    assert_eq!([2., 0.5, 0.25], nbs3d.cell_sizes);
+    assert_eq!(5 * 2 * 4, nbs3d.total_no_cells);
+
+    let nbs2d = NbsBuilder::new()
+        .x_limits(0., 10.)
+        .y_limits(0., 1.)
+        .cell_sizes(2., 0.5, 0.)
+        .build_2d()
+        .unwrap();
+    assert_eq!([2., 0.5], nbs2d.cell_sizes);
+}
+
+#[test]
+fn test_builder_checks_the_rounded_up_cell_count() {
+    // 2^60 - 2^40 cells when the edge is clamped, one cell more along z and
+    // so 2^60 cells when rounding up, past what head can address
+    let side = (1u64 << 20) as f64;
+    let builder = NbsBuilder::new()
+        .x_limits(0., side)
+        .y_limits(0., side)
+        .z_limits(0., side - 0.5)
+        .cell_size(1.)
+        .cell_count_policy(CellCountPolicy::RoundUp);
+    assert_eq!(
+        Err(NnpsError::CellCountOverflow),
+        builder.build_3d().map(|grid| grid.total_no_cells)
+    );
+}
@@ -0,0 +1,162 @@
+extern crate neighbours;
+
+mod common;
+use common::scattered;
+
+// local library imports
+use neighbours::aabb::Aabb;
+use neighbours::auto_fit::AutoFit;
+use neighbours::builder::NbsBuilder;
+use neighbours::cell_ordering::{CellOrdering, MAX_MORTON_SPARSITY};
+use neighbours::morton;
+use neighbours::nbs2d::NBS2D;
+use neighbours::nbs3d::NBS3D;
+use neighbours::registration::RegistrationError;
+use neighbours::{NnpsError, NNPS};
+
+#[test]
+fn test_cell_ordering_morton_keys_round_trip() {
+    for &(x, y) in &[(0, 0), (1, 0), (3, 2), (12345, 678), (u32::MAX, 7)] {
+        assert_eq!((x, y), morton::decode_2d(morton::encode_2d(x, y)));
+    }
+    for &(x, y, z) in &[(0, 0, 0), (1, 2, 3), (2097151, 0, 1048576), (77, 5000, 31)] {
+        assert_eq!((x, y, z), morton::decode_3d(morton::encode_3d(x, y, z)));
+    }
+    assert_eq!([2, 3], morton::decode::<2>(0b1110));
+    assert_eq!([9], morton::decode::<1>(9));
+
+    // the indices of the two orderings on a 4 x 4 grid
+    let no_cells = [4, 4];
+    assert_eq!(13, CellOrdering::RowMajor.index([1, 3], no_cells));
+    assert_eq!(11, CellOrdering::Morton.index([1, 3], no_cells));
+    assert_eq!([1, 3], CellOrdering::RowMajor.cell(13, no_cells));
+    assert_eq!([1, 3], CellOrdering::Morton.cell(11, no_cells));
+}
+
+#[test]
+fn test_cell_ordering_morton_head_covers_every_key() {
+    let mut nbs2d = NBS2D::new(0., 3., 0., 5., 1.);
+    assert_eq!(15, nbs2d.head.len());
+
+    // the last cell (2, 4) has the key 0b100100
+    nbs2d.set_cell_ordering(CellOrdering::Morton);
+    assert_eq!(CellOrdering::Morton, nbs2d.cell_ordering);
+    assert_eq!(15, nbs2d.total_no_cells);
+    assert_eq!(37, nbs2d.head.len());
+
+    let x = vec![0.5, 2.5, 1.5];
+    let y = vec![0.5, 4.5, 1.5];
+    nbs2d.initialize_next(x.len());
+    nbs2d.register_particles_to_nnps(&x, &y, &[]);
+    assert_eq!(0, nbs2d.head[0]);
+    assert_eq!(2, nbs2d.head[3]);
+    assert_eq!(1, nbs2d.head[36]);
+
+    nbs2d.set_cell_ordering(CellOrdering::RowMajor);
+    assert_eq!(15, nbs2d.head.len());
+}
+
+#[test]
+fn test_cell_ordering_morton_grid_finds_the_same_neighbours() {
+    let x = scattered(400, 1);
+    let y = scattered(400, 2);
+    let z = scattered(400, 3);
+    let h = 0.15;
+
+    let builder = NbsBuilder::new()
+        .domain(Aabb::new([0.; 3], [1.; 3]))
+        .cell_size(h)
+        .periodic_x(true)
+        .no_of_particles(x.len());
+    let mut row_major = builder.build_3d().unwrap();
+    let mut z_order = builder
+        .cell_ordering(CellOrdering::Morton)
+        .build_3d()
+        .unwrap();
+    assert_eq!(CellOrdering::Morton, z_order.cell_ordering);
+    row_major.register_particles_to_nnps(&x, &y, &z);
+    z_order.register_particles_to_nnps(&x, &y, &z);
+
+    for i in 0..x.len() {
+        let mut expected = row_major.get_neighbours_with_shifts(x[i], y[i], z[i]);
+        let mut nbrs = z_order.get_neighbours_with_shifts(x[i], y[i], z[i]);
+        expected.sort_by_key(|&(j, _)| j);
+        nbrs.sort_by_key(|&(j, _)| j);
+        assert_eq!(expected, nbrs);
+    }
+
+    let mut expected: Vec<(usize, usize)> = row_major.pairs().collect();
+    let mut pairs: Vec<(usize, usize)> = z_order.pairs().collect();
+    expected.sort();
+    pairs.sort();
+    assert_eq!(expected, pairs);
+}
+
+#[test]
+fn test_cell_ordering_morton_key_overflow_is_reported() {
+    // more cells along x than a 3D Morton key has room for
+    let mut nbs3d = NBS3D::new(0., (1 << 21) as f64 + 1., 0., 1., 0., 1., 1.);
+    assert_eq!(
+        Err(NnpsError::CellCountOverflow),
+        nbs3d.try_set_cell_ordering(CellOrdering::Morton)
+    );
+    assert_eq!(CellOrdering::RowMajor, nbs3d.cell_ordering);
+}
+
+#[test]
+fn test_cell_ordering_morton_rejects_elongated_grids() {
+    // 9000 cells, but Morton keys up to about 153 million
+    let mut nbs3d = NBS3D::new(0., 1000., 0., 3., 0., 3., 1.);
+    assert_eq!(
+        Err(NnpsError::CellCountOverflow),
+        nbs3d.try_set_cell_ordering(CellOrdering::Morton)
+    );
+    assert_eq!(CellOrdering::RowMajor, nbs3d.cell_ordering);
+    assert_eq!(9000, nbs3d.head.len());
+
+    let mut nbs2d = NBS2D::new(0., 4096., 0., 3., 1.);
+    assert_eq!(
+        Err(NnpsError::CellCountOverflow),
+        nbs2d.try_set_cell_ordering(CellOrdering::Morton)
+    );
+    let builder = NbsBuilder::new()
+        .x_limits(0., 4096.)
+        .y_limits(0., 3.)
+        .cell_size(1.)
+        .cell_ordering(CellOrdering::Morton);
+    assert_eq!(
+        Err(NnpsError::CellCountOverflow),
+        builder.build_2d().map(|grid| grid.head.len())
+    );
+
+    // a grid a few times longer than wide keeps a compact head
+    let mut nbs2d = NBS2D::new(0., 12., 0., 3., 1.);
+    nbs2d.set_cell_ordering(CellOrdering::Morton);
+    assert!(nbs2d.head.len() <= MAX_MORTON_SPARSITY * nbs2d.total_no_cells);
+}
+
+#[test]
+fn test_cell_ordering_morton_with_auto_fit() {
+    let mut nbs2d = NBS2D::new(0., 4., 0., 4., 1.);
+    nbs2d.set_cell_ordering(CellOrdering::Morton);
+    nbs2d.set_auto_fit(Some(AutoFit::new(0., 0.)));
+
+    // growing a little keeps the Morton keys compact
+    let report = nbs2d
+        .register_particles_checked(&[0.5, 5.5], &[0.5, 0.5], &[])
+        .unwrap();
+    assert!(report.is_clean());
+    assert_eq!([6, 4], nbs2d.no_cells);
+    assert_eq!(vec![1], nbs2d.get_neighbours(5.5, 0.5, 0.));
+
+    // stretching the grid to 1001 x 4 cells would make head mostly gaps
+    let (domain, head_len) = (nbs2d.domain, nbs2d.head.len());
+    assert_eq!(
+        Err(RegistrationError::Nnps(NnpsError::CellCountOverflow)),
+        nbs2d.register_particles_checked(&[0.5, 1000.], &[0.5, 0.5], &[])
+    );
+    assert_eq!(domain, nbs2d.domain);
+    assert_eq!([6, 4], nbs2d.no_cells);
+    assert_eq!(head_len, nbs2d.head.len());
+    assert_eq!(CellOrdering::Morton, nbs2d.cell_ordering);
+}
@@ -0,0 +1,55 @@
+extern crate neighbours;
+
+// local library imports
+use neighbours::nbs2d::NBS2D;
+use neighbours::nbs3d::NBS3D;
+use neighbours::NNPS;
+
+#[test]
+fn test_csr_neighbour_list_of_nbs2d() {
+    let x = vec![0.5, 0.9, 1.6, 2.5];
+    let y = vec![0.5, 0.5, 0.5, 0.5];
+
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 3., 0., 3., 1., x.len());
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+    let csr = nbs2d.get_neighbours_csr(&x, &y, &[0.], 0.95, true, false);
+    assert_eq!(4, csr.len());
+    assert_eq!(vec![0, 1, 3, 5, 6], csr.offsets);
+    assert_eq!(vec![1, 0, 2, 1, 3, 2], csr.indices);
+    assert_eq!(None, csr.distances);
+    assert_eq!(&[1, 3], csr.neighbours(2));
+    assert_eq!(None, csr.distances(2));
+
+    // every list matches the single particle query
+    for i in 0..x.len() {
+        let mut nbrs = nbs2d.get_neighbours_within(i, &x, &y, &[0.], 0.95, true);
+        let mut from_csr = csr.neighbours(i).to_vec();
+        nbrs.sort();
+        from_csr.sort();
+        assert_eq!(nbrs, from_csr);
+    }
+}
+
+#[test]
+fn test_csr_neighbour_list_of_nbs3d_with_distances() {
+    let x = vec![0.5, 0.5, 0.5];
+    let y = vec![0.5, 0.5, 0.5];
+    let z = vec![0.5, 1.0, 2.9];
+
+    let mut nbs3d = NBS3D::new(0., 3., 0., 3., 0., 3., 1.);
+    nbs3d.initialize_next(x.len());
+    nbs3d.set_periodic(false, false, true);
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    let csr = nbs3d.get_neighbours_csr(&x, &y, &z, 0.7, false, true);
+    assert_eq!(vec![0, 3, 5, 7], csr.offsets);
+
+    // particle 2 is 0.6 away from particle 0 through the periodic face
+    let distances = csr.distances(0).unwrap();
+    for (&j, &r) in csr.neighbours(0).iter().zip(distances) {
+        let expected = [0., 0.5, 0.6][j];
+        assert!((r - expected).abs() < 1e-12);
+    }
+    assert_eq!(csr.indices.len(), csr.distances.as_ref().unwrap().len());
+}
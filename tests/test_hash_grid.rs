@@ -0,0 +1,92 @@
+extern crate neighbours;
+
+mod common;
+use common::{brute_force, scattered};
+
+// local library imports
+use neighbours::hash_grid::HashGrid;
+use neighbours::NNPS;
+
+#[test]
+fn test_hash_grid_unbounded_3d_against_brute_force() {
+    // two far apart clouds, on both sides of the origin
+    let mut x = scattered(600, 1);
+    let mut y = scattered(600, 2);
+    let mut z = scattered(600, 3);
+    for i in 0..300 {
+        x[i] = 1e6 + 10. * x[i];
+        y[i] = -1e6 - 10. * y[i];
+        z[i] *= -5.;
+    }
+
+    let h = 0.3;
+    let mut grid = HashGrid::new(3, h);
+    grid.register_particles_to_nnps(&x, &y, &z);
+
+    // every particle is registered, and only the occupied cells are stored
+    assert!(grid.no_occupied_cells() <= x.len());
+
+    for i in 0..x.len() {
+        let mut found = grid.get_neighbours_within(i, &x, &y, &z, h, false);
+        found.sort_unstable();
+        assert_eq!(brute_force([&x, &y, &z], [x[i], y[i], z[i]], h), found);
+    }
+}
+
+#[test]
+fn test_hash_grid_2d_ignores_z() {
+    let x = vec![-0.5, 0.4, 1.2, -3.1, 100.];
+    let y = vec![0.1, -0.2, 0.3, 0.0, 100.];
+    let z = vec![0.];
+
+    let mut grid = HashGrid::new(2, 1.);
+    grid.register_particles_to_nnps(&x, &y, &z);
+    assert_eq!(5, grid.no_occupied_cells());
+
+    // the cells around the origin hold the first three particles
+    let mut nbrs = grid.get_neighbours(0.1, 0.1, 7.);
+    nbrs.sort_unstable();
+    assert_eq!(vec![0, 1, 2], nbrs);
+
+    assert_eq!(vec![4], grid.get_neighbours(100.5, 99.5, 0.));
+    assert!(grid.get_neighbours(50., 50., 0.).is_empty());
+
+    let within = grid.get_neighbours_within(0, &x, &y, &z, 1., true);
+    assert_eq!(vec![1], within);
+}
+
+#[test]
+fn test_hash_grid_skips_non_finite_particles() {
+    let x = vec![0.1, f64::NAN, 0.2, f64::INFINITY];
+    let y = vec![0.1, 0.1, 0.2, 0.1];
+    let z = vec![0.1, 0.1, 0.2, 0.1];
+
+    let mut grid = HashGrid::new(3, 1.);
+    grid.register_particles_to_nnps(&x, &y, &z);
+
+    let mut nbrs = grid.get_neighbours(0.1, 0.1, 0.1);
+    nbrs.sort_unstable();
+    assert_eq!(vec![0, 2], nbrs);
+    assert!(grid.get_neighbours(f64::NAN, 0., 0.).is_empty());
+
+    // registering again forgets the old cells
+    grid.register_particles_to_nnps(&[5.], &[5.], &[5.]);
+    assert_eq!(1, grid.no_occupied_cells());
+    assert!(grid.get_neighbours(0.1, 0.1, 0.1).is_empty());
+}
+
+#[test]
+fn test_hash_grid_far_away_particles_saturate_the_keys() {
+    // finite but far past the range of the cell keys, on both sides
+    let x = vec![1e300, 0.1, -1e300, 1e300];
+    let y = vec![0.1, 0.1, -1e300, 1e300];
+    let z = vec![0.1, 0.1, 0.1, -1e300];
+
+    let mut grid = HashGrid::new(3, 1.);
+    grid.register_particles_to_nnps(&x, &y, &z);
+
+    assert_eq!(vec![0], grid.get_neighbours(1e300, 0.1, 0.1));
+    assert_eq!(vec![2], grid.get_neighbours(-1e300, -1e300, 0.1));
+    assert_eq!(vec![3], grid.get_neighbours(1e300, 1e300, -1e300));
+    assert_eq!(vec![1], grid.get_neighbours_within(1, &x, &y, &z, 1., false));
+}
@@ -0,0 +1,102 @@
+extern crate neighbours;
+
+mod common;
+use common::scattered;
+
+// local library imports
+use neighbours::kdtree::KdTree;
+use neighbours::NNPS;
+
+/// All the particles sorted by their distance to `point`.
+fn sorted_by_distance(x: &[f64], y: &[f64], z: &[f64], point: [f64; 3]) -> Vec<(usize, f64)> {
+    let mut all: Vec<(usize, f64)> = (0..x.len())
+        .map(|i| {
+            let dx = x[i] - point[0];
+            let dy = y[i] - point[1];
+            let dz = z[i] - point[2];
+            (i, (dx * dx + dy * dy + dz * dz).sqrt())
+        })
+        .collect();
+    all.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    all
+}
+
+#[test]
+fn test_kdtree_k_nearest_against_brute_force() {
+    let x = scattered(1500, 1);
+    let y = scattered(1500, 2);
+    let z = scattered(1500, 3);
+
+    let mut tree = KdTree::new(3, 0.1);
+    tree.register_particles_to_nnps(&x, &y, &z);
+
+    for i in (0..x.len()).step_by(11) {
+        let expected = sorted_by_distance(&x, &y, &z, [x[i], y[i], z[i]]);
+        let nearest = tree.get_k_nearest_with_distances(x[i], y[i], z[i], 10);
+        assert_eq!(10, nearest.len());
+        // the particle itself comes first
+        assert_eq!((i, 0.), nearest[0]);
+        for (found, expected) in nearest.iter().zip(&expected) {
+            assert_eq!(expected.0, found.0);
+            assert!((expected.1 - found.1).abs() < 1e-12);
+        }
+    }
+
+    // a query point away from the particles
+    let nearest = tree.get_k_nearest(2., 2., 2., 3);
+    let expected: Vec<usize> = sorted_by_distance(&x, &y, &z, [2., 2., 2.])
+        .into_iter()
+        .take(3)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(expected, nearest);
+
+    // asking for more particles than registered
+    assert_eq!(
+        x.len(),
+        tree.get_k_nearest(0.5, 0.5, 0.5, 2 * x.len()).len()
+    );
+    assert!(tree.get_k_nearest(0.5, 0.5, 0.5, 0).is_empty());
+}
+
+#[test]
+fn test_kdtree_radius_queries_against_brute_force() {
+    let x = scattered(1000, 4);
+    let y = scattered(1000, 5);
+    let z = vec![0.; x.len()];
+
+    // in 2D the z slice is ignored
+    let mut tree = KdTree::new(2, 0.05);
+    tree.register_particles_to_nnps(&x, &y, &[0.]);
+
+    for i in (0..x.len()).step_by(7) {
+        let mut expected: Vec<usize> = sorted_by_distance(&x, &y, &z, [x[i], y[i], 0.])
+            .into_iter()
+            .filter(|&(_, r)| r <= 0.05)
+            .map(|(j, _)| j)
+            .collect();
+        expected.sort();
+
+        let mut nbrs = tree.get_neighbours(x[i], y[i], 0.);
+        nbrs.sort();
+        assert_eq!(expected, nbrs);
+
+        let mut nbrs = tree.get_neighbours_within(i, &x, &y, &[0.], 0.05, true);
+        nbrs.sort();
+        expected.retain(|&j| j != i);
+        assert_eq!(expected, nbrs);
+    }
+}
+
+#[test]
+fn test_kdtree_without_particles() {
+    let mut tree = KdTree::new(3, 0.1);
+    tree.register_particles_to_nnps(&[], &[], &[]);
+
+    assert!(tree.get_neighbours(0., 0., 0.).is_empty());
+    assert!(tree.get_k_nearest(0., 0., 0., 4).is_empty());
+
+    // particles with non finite positions are left out
+    tree.register_particles_to_nnps(&[f64::NAN, 0.], &[0., 0.], &[0., f64::INFINITY]);
+    assert!(tree.get_k_nearest(0., 0., 0., 4).is_empty());
+}
@@ -0,0 +1,109 @@
+extern crate neighbours;
+
+mod common;
+use common::brute_force;
+
+// local library imports
+use neighbours::multi_array::MultiArrayGrid;
+use neighbours::nbs2d::NBS2D;
+use neighbours::particle_array::ParticleArray;
+
+const FLUID: usize = 0;
+const WALL: usize = 7;
+
+/// Fluid particles in `[0, 1) x [0.2, 1)` and a row of wall particles along
+/// `y = 0.1`.
+fn fluid_and_wall() -> (ParticleArray, ParticleArray) {
+    let mut fluid = ParticleArray::new(0);
+    for j in 0..8 {
+        for i in 0..10 {
+            fluid.x.push(0.05 + 0.1 * i as f64);
+            fluid.y.push(0.25 + 0.1 * j as f64);
+            fluid.z.push(0.);
+            fluid.radius.push(0.05);
+        }
+    }
+    let x: Vec<f64> = (0..20).map(|i| 0.025 + 0.05 * i as f64).collect();
+    let wall = ParticleArray::from_xyz_rad(&x, &[0.1; 20], &[0.; 20], &[0.025; 20]);
+    (fluid, wall)
+}
+
+/// Neighbours of particle `i` of `destination` among `source`, tagged with
+/// the ID of `source`.
+fn brute_force_from(
+    destination: &ParticleArray,
+    i: usize,
+    source: &ParticleArray,
+    id: usize,
+    h: f64,
+) -> Vec<(usize, usize)> {
+    let point = [destination.x[i], destination.y[i]];
+    brute_force([&source.x, &source.y], point, h)
+        .into_iter()
+        .map(|j| (id, j))
+        .collect()
+}
+
+#[test]
+fn test_multi_array_fluid_against_wall() {
+    let (fluid, wall) = fluid_and_wall();
+    let h = 0.2;
+    let mut grid = MultiArrayGrid::new(NBS2D::new(0., 1., 0., 1., h));
+    grid.register_arrays(&[(FLUID, &fluid), (WALL, &wall)]);
+    assert_eq!(vec![FLUID, WALL], grid.ids);
+    assert_eq!(vec![0, 80, 100], grid.offsets);
+    assert_eq!(20, grid.no_of_particles(WALL));
+
+    let mut found_wall = false;
+    for i in 0..fluid.x.len() {
+        // the wall neighbours of the fluid particles
+        let mut nbrs = grid.get_neighbours_within(FLUID, i, Some(WALL), h, false);
+        nbrs.sort();
+        assert_eq!(brute_force_from(&fluid, i, &wall, WALL, h), nbrs);
+        found_wall |= !nbrs.is_empty();
+
+        // the fluid neighbours, the particle itself left out
+        let mut nbrs = grid.get_neighbours_within(FLUID, i, Some(FLUID), h, true);
+        nbrs.sort();
+        let expected: Vec<(usize, usize)> = brute_force_from(&fluid, i, &fluid, FLUID, h)
+            .into_iter()
+            .filter(|&(_, j)| j != i)
+            .collect();
+        assert_eq!(expected, nbrs);
+    }
+    assert!(found_wall);
+
+    // the fluid neighbours of the wall particles, through every array
+    for i in 0..wall.x.len() {
+        let mut nbrs = grid.get_neighbours_within(WALL, i, None, h, false);
+        nbrs.sort();
+        let mut expected = brute_force_from(&wall, i, &fluid, FLUID, h);
+        expected.extend(brute_force_from(&wall, i, &wall, WALL, h));
+        expected.sort();
+        assert_eq!(expected, nbrs);
+    }
+}
+
+#[test]
+fn test_multi_array_registration_replaces_the_arrays() {
+    let (fluid, wall) = fluid_and_wall();
+    let mut grid = MultiArrayGrid::new(NBS2D::new(0., 1., 0., 1., 0.2));
+    grid.register_arrays(&[(FLUID, &fluid), (WALL, &wall)]);
+    grid.register_arrays(&[(WALL, &wall)]);
+    assert_eq!(vec![WALL], grid.ids);
+    assert_eq!(20, grid.grid.next.len());
+
+    // the first wall particle only sees its neighbours along the wall
+    let mut nbrs = grid.get_neighbours_within(WALL, 0, None, 0.06, true);
+    nbrs.sort();
+    assert_eq!(vec![(WALL, 1)], nbrs);
+}
+
+#[test]
+#[should_panic(expected = "no particle array is registered with the id 3")]
+fn test_multi_array_unknown_id_panics() {
+    let (fluid, _) = fluid_and_wall();
+    let mut grid = MultiArrayGrid::new(NBS2D::new(0., 1., 0., 1., 0.2));
+    grid.register_arrays(&[(FLUID, &fluid)]);
+    grid.get_neighbours_within(FLUID, 0, Some(3), 0.2, false);
+}
@@ -0,0 +1,132 @@
+extern crate neighbours;
+
+mod common;
+use common::brute_force;
+
+// local library imports
+use neighbours::auto_fit::AutoFit;
+use neighbours::builder::NbsBuilder;
+use neighbours::cell_count::CellCountPolicy;
+use neighbours::nbs1d::NBS1D;
+use neighbours::NNPS;
+
+/// Particles of a shock tube: a dense left state on `[0, 0.5)` and a light
+/// right state on `[0.5, 1)`, four times sparser.
+fn shock_tube() -> Vec<f64> {
+    let mut x: Vec<f64> = (0..80).map(|i| i as f64 * 0.5 / 80.).collect();
+    x.extend((0..20).map(|i| 0.5 + i as f64 * 0.5 / 20.));
+    x
+}
+
+#[test]
+fn test_nbs1d_creation_for_a_given_domain_limits() {
+    let nbs1d = NBS1D::new(0., 1., 0.1);
+    assert_eq!(10, nbs1d.head.len());
+    assert_eq!([10], nbs1d.no_cells);
+    assert_eq!(0, nbs1d.next.len());
+
+    assert!(NBS1D::try_new(1., 0., 0.1).is_err());
+}
+
+#[test]
+fn test_nbs1d_shock_tube_neighbours_against_brute_force() {
+    let x = shock_tube();
+    let h = 0.051;
+    let mut nbs1d = NBS1D::from_limits_and_no_of_particles(0., 1., h, x.len());
+    // the y and z coordinates are never looked at in 1D
+    nbs1d.register_particles_to_nnps(&x, &[], &[]);
+
+    for i in 0..x.len() {
+        let mut nbrs = nbs1d.get_neighbours_within(i, &x, &[], &[], h, false);
+        nbrs.sort();
+        assert_eq!(brute_force([&x], [x[i]], h), nbrs);
+    }
+
+    // the dense side has four times more neighbours than the light one
+    assert_eq!(
+        17,
+        nbs1d
+            .get_neighbours_within(40, &x, &[], &[], h, false)
+            .len()
+    );
+    assert_eq!(
+        5,
+        nbs1d
+            .get_neighbours_within(90, &x, &[], &[], h, false)
+            .len()
+    );
+}
+
+#[test]
+fn test_nbs1d_stencil_visits_the_home_cell_then_the_left_and_right_cells() {
+    let x = vec![0.5, 1.5, 2.5, 3.5];
+    let mut nbs1d = NBS1D::from_limits_and_no_of_particles(0., 4., 1., x.len());
+    nbs1d.register_particles_to_nnps(&x, &[], &[]);
+
+    assert_eq!(vec![1, 0, 2], nbs1d.get_neighbours(1.5, 0., 0.));
+    // the last cell has no right neighbour
+    assert_eq!(vec![3, 2], nbs1d.get_neighbours(3.5, 0., 0.));
+    // out of the domain
+    assert!(nbs1d.get_neighbours(4.5, 0., 0.).is_empty());
+}
+
+#[test]
+fn test_nbs1d_periodic_tube() {
+    let x = vec![0.05, 0.5, 0.97];
+    let mut nbs1d = NBS1D::from_limits_and_no_of_particles(0., 1., 0.1, x.len());
+    nbs1d.set_periodic(true);
+    nbs1d.register_particles_to_nnps(&x, &[], &[]);
+
+    // the particle at the left end sees the one at the right end one tube
+    // length to the left
+    let nbrs = nbs1d.get_neighbours_with_shifts(0.05, 0., 0.);
+    assert_eq!(vec![(0, [0.]), (2, [-1.])], nbrs);
+    assert_eq!(
+        vec![(0, 2)],
+        nbs1d.pairs_within(&x, 0.1).collect::<Vec<_>>()
+    );
+
+    assert!(NBS1D::new(0., 1., 0.5).try_set_periodic(true).is_err());
+}
+
+#[test]
+fn test_nbs1d_pairs_against_brute_force() {
+    let x = shock_tube();
+    let h = 0.03;
+    let nbs1d = NbsBuilder::new()
+        .x_limits(0., 1.)
+        .cell_size(h)
+        .no_of_particles(x.len())
+        .build_1d();
+    let mut nbs1d = nbs1d.unwrap();
+    nbs1d.register_particles_to_nnps(&x, &[], &[]);
+
+    let mut pairs: Vec<(usize, usize)> = nbs1d.pairs_within(&x, h).collect();
+    pairs.sort();
+    let mut expected_pairs = vec![];
+    for i in 0..x.len() {
+        for j in brute_force([&x], [x[i]], h) {
+            if i < j {
+                expected_pairs.push((i, j));
+            }
+        }
+    }
+    assert_eq!(expected_pairs, pairs);
+}
+
+#[test]
+fn test_nbs1d_auto_fit_with_a_rounded_up_cell_count() {
+    // the third cell reaches x = 3, past the upper limit
+    let mut nbs1d = NBS1D::new(0., 2.5, 1.);
+    nbs1d.set_cell_count_policy(CellCountPolicy::RoundUp);
+    nbs1d.set_auto_fit(Some(AutoFit::new(0., 0.)));
+    assert_eq!(3, nbs1d.no_cells[0]);
+
+    // a particle in the last cell but past the upper limit grows the domain
+    // instead of being lost
+    let x = vec![0.5, 2.7];
+    let report = nbs1d.register_particles_checked(&x, &[], &[]).unwrap();
+    assert!(report.is_clean());
+    assert!(nbs1d.domain.max[0] >= 2.7);
+    assert_eq!(vec![1], nbs1d.get_neighbours(2.7, 0., 0.));
+}
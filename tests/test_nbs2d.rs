@@ -1,8 +1,17 @@
+// lints raised by the original tests
+#![allow(clippy::useless_vec, clippy::legacy_numeric_constants, unused_variables)]
 extern crate neighbours;
 
 // local library imports
+use neighbours::aabb::Aabb;
+use neighbours::auto_fit::AutoFit;
+use neighbours::builder::NbsBuilder;
+use neighbours::cell_count::CellCountPolicy;
+use neighbours::hash_grid::HashGrid;
+use neighbours::kdtree::KdTree;
 use neighbours::nbs2d::NBS2D;
-use neighbours::NNPS;
+use neighbours::registration::RegistrationError;
+use neighbours::{NnpsError, NNPS};
 
 
 #[test]
@@ -169,13 +178,15 @@ fn test_nbs2d_get_neighbours_25_cells_with_a_single_point_in_some_cells() {
     let expected_neighbours = vec![12, 11, 13, 7, 6, 8, 17, 16, 18];
     assert_eq!(expected_neighbours, nbrs);
 
+    // the cells of the previous row and column are off the grid, they must
+    // not wrap around to the end of the first row
     let nbrs = nbs2d.get_neighbours(0.0, 0.1, 0.);
-    let expected_neighbours = vec![0, 1, 5, 4, 6];
+    let expected_neighbours = vec![0, 1, 5, 6];
     assert_eq!(expected_neighbours, nbrs);
 
     // particle in the final cell
     let nbrs = nbs2d.get_neighbours(4.999, 4.999, 0.);
-    let expected_neighbours = vec![24, 23, 19, 18, 20];
+    let expected_neighbours = vec![24, 23, 19, 18];
     assert_eq!(expected_neighbours, nbrs);
 
     // check the particle which is out of domain
@@ -262,3 +273,520 @@ fn test_nbs2d_10_particles_on_x_axis() {
     // ];
     // assert_eq!(expected_neighbours, nbrs);
 }
+
+
+#[test]
+fn test_nbs2d_periodic_neighbours_across_the_x_face() {
+    // the dimensions of the simulation
+    let x_min = 0.;
+    let x_max = 5.;
+    let y_min = 0.;
+    let y_max = 5.;
+    let max_size = 1.0;
+    // one particle near the left face and one near the right face
+    let x = vec![0.1, 4.9, 2.5];
+    let y = vec![2.5, 2.5, 0.1];
+
+    let mut nbs2d =
+        NBS2D::from_limits_and_no_of_particles(x_min, x_max, y_min, y_max, max_size, x.len());
+    nbs2d.set_periodic(true, false);
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+    let nbrs = nbs2d.get_neighbours(0.1, 2.5, 0.);
+    assert_eq!(vec![0, 1], nbrs);
+
+    // the particle on the right face is seen one domain length to the left
+    let nbrs = nbs2d.get_neighbours_with_shifts(0.1, 2.5, 0.);
+    assert_eq!(vec![(0, [0., 0.]), (1, [-5., 0.])], nbrs);
+
+    let nbrs = nbs2d.get_neighbours_with_shifts(4.9, 2.5, 0.);
+    assert_eq!(vec![(1, [0., 0.]), (0, [5., 0.])], nbrs);
+
+    // y is not periodic, so nothing is found across the y faces
+    let nbrs = nbs2d.get_neighbours(2.5, 4.9, 0.);
+    let expected_neighbours: Vec<usize> = vec![];
+    assert_eq!(expected_neighbours, nbrs);
+}
+
+#[test]
+fn test_nbs2d_periodic_registration_wraps_particles_into_the_domain() {
+    let x = vec![-0.5, 5.5];
+    let y = vec![2.5, -0.5];
+
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 5., 0., 5., 1., x.len());
+    nbs2d.set_periodic(true, true);
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+    // -0.5 is wrapped to 4.5 and 5.5 to 0.5
+    assert_eq!(0, nbs2d.head[2 * 5 + 4]);
+    assert_eq!(1, nbs2d.head[4 * 5]);
+
+    // the corner particle is found diagonally across both faces
+    let nbrs = nbs2d.get_neighbours_with_shifts(4.5, 0.5, 0.);
+    assert_eq!(vec![(1, [5., -5.])], nbrs);
+    let nbrs = nbs2d.get_neighbours_with_shifts(0.5, 4.5, 0.);
+    assert_eq!(vec![(1, [0., 0.])], nbrs);
+}
+
+#[test]
+#[should_panic]
+fn test_nbs2d_periodic_axis_with_less_than_3_cells() {
+    let mut nbs2d = NBS2D::new(0., 2., 0., 5., 1.);
+    nbs2d.set_periodic(true, false);
+}
+
+/// Particles on a lattice with one particle at a quarter and one at three
+/// quarters of every cell, in both directions.
+fn lattice_2d(no_x_cells: usize, no_y_cells: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut x = vec![];
+    let mut y = vec![];
+    for j in 0..2 * no_y_cells {
+        for i in 0..2 * no_x_cells {
+            x.push(0.25 + 0.5 * i as f64);
+            y.push(0.25 + 0.5 * j as f64);
+        }
+    }
+    (x, y)
+}
+
+/// Brute force neighbours, all the particles whose cell is at most one cell
+/// away from the cell of the query point along each axis.
+fn brute_force_2d(x: &[f64], y: &[f64], xq: f64, yq: f64) -> Vec<usize> {
+    let (nx, ny) = (xq.floor() as isize, yq.floor() as isize);
+    let mut neighbours: Vec<usize> = (0..x.len())
+        .filter(|&i| {
+            (x[i].floor() as isize - nx).abs() <= 1 && (y[i].floor() as isize - ny).abs() <= 1
+        })
+        .collect();
+    neighbours.sort();
+    neighbours
+}
+
+#[test]
+fn test_nbs2d_get_neighbours_on_boundary_cells_against_brute_force() {
+    for &(no_x_cells, no_y_cells) in &[(5, 4), (1, 4), (4, 1), (1, 1), (2, 3)] {
+        let (x, y) = lattice_2d(no_x_cells, no_y_cells);
+        let mut nbs2d = NBS2D::from_limits_and_no_of_particles(
+            0.,
+            no_x_cells as f64,
+            0.,
+            no_y_cells as f64,
+            1.,
+            x.len(),
+        );
+        nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+        // query from the particle positions, so that every corner and edge
+        // cell is visited
+        for i in 0..x.len() {
+            let mut nbrs = nbs2d.get_neighbours(x[i], y[i], 0.);
+            nbrs.sort();
+            assert_eq!(brute_force_2d(&x, &y, x[i], y[i]), nbrs);
+        }
+    }
+}
+
+#[test]
+fn test_nbs2d_get_neighbours_within_a_radius() {
+    let x = vec![0.5, 0.9, 1.6, 2.5];
+    let y = vec![0.5, 0.5, 0.5, 0.5];
+
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 3., 0., 3., 1., x.len());
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+    // the cells of particle 0 also hold particle 2, but it is 1.1 away
+    let mut nbrs = nbs2d.get_neighbours_within(0, &x, &y, &[0.], 1., false);
+    nbrs.sort();
+    assert_eq!(vec![0, 1], nbrs);
+
+    let nbrs = nbs2d.get_neighbours_within(0, &x, &y, &[0.], 1., true);
+    assert_eq!(vec![1], nbrs);
+
+    let mut nbrs = nbs2d.get_neighbours_within(2, &x, &y, &[0.], 0.9, true);
+    nbrs.sort();
+    assert_eq!(vec![1, 3], nbrs);
+}
+
+#[test]
+fn test_nbs2d_get_neighbours_within_a_radius_across_a_periodic_face() {
+    // particle 1 is outside the domain, it is wrapped to x = 4.8
+    let x = vec![0.2, -0.2, 2.5];
+    let y = vec![2.5, 2.5, 2.5];
+
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 5., 0., 5., 1., x.len());
+    nbs2d.set_periodic(true, true);
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+    let nbrs = nbs2d.get_neighbours_within(0, &x, &y, &[0.], 0.5, true);
+    assert_eq!(vec![1], nbrs);
+    let nbrs = nbs2d.get_neighbours_within(1, &x, &y, &[0.], 0.5, true);
+    assert_eq!(vec![0], nbrs);
+    let nbrs = nbs2d.get_neighbours_within(0, &x, &y, &[0.], 0.3, true);
+    let expected_neighbours: Vec<usize> = vec![];
+    assert_eq!(expected_neighbours, nbrs);
+}
+
+#[test]
+fn test_nbs2d_neighbours_iter_and_get_neighbours_into() {
+    let (x, y) = lattice_2d(4, 4);
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 4., 0., 4., 1., x.len());
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+    // the same buffer is reused for all the queries
+    let mut nbrs = vec![usize::MAX; 100];
+    for i in 0..x.len() {
+        let expected_neighbours = nbs2d.get_neighbours(x[i], y[i], 0.);
+        nbs2d.get_neighbours_into(x[i], y[i], 0., &mut nbrs);
+        assert_eq!(expected_neighbours, nbrs);
+
+        let iterated: Vec<usize> = nbs2d.neighbours_iter(x[i], y[i], 0.).collect();
+        assert_eq!(expected_neighbours, iterated);
+    }
+
+    // a query out of the domain clears the buffer
+    nbs2d.get_neighbours_into(-1., 0.5, 0., &mut nbrs);
+    assert!(nbrs.is_empty());
+    assert_eq!(None, nbs2d.neighbours_iter(-1., 0.5, 0.).next());
+}
+
+#[test]
+fn test_nbs2d_backends_behind_a_trait_object() {
+    let (x, y) = lattice_2d(4, 4);
+    let z = vec![0.; x.len()];
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 4., 0., 4., 1., x.len());
+    nbs2d.register_particles_to_nnps(&x, &y, &z);
+
+    // the backend is picked at run time
+    let mut backends: Vec<Box<dyn NNPS>> = vec![
+        Box::new(NBS2D::from_limits_and_no_of_particles(0., 4., 0., 4., 1., x.len())),
+        Box::new(HashGrid::new(2, 1.)),
+        Box::new(KdTree::new(2, 1.)),
+    ];
+    for backend in backends.iter_mut() {
+        backend.register_particles_to_nnps(&x, &y, &z);
+    }
+
+    let mut nbrs = vec![];
+    for i in 0..x.len() {
+        assert_eq!(
+            nbs2d.get_neighbours(x[i], y[i], 0.),
+            backends[0].get_neighbours(x[i], y[i], 0.)
+        );
+        // the other backends find at least the particles within the cell size
+        let within = nbs2d.get_neighbours_within(i, &x, &y, &z, 1., false);
+        for backend in &backends[1..] {
+            backend.get_neighbours_into(x[i], y[i], 0., &mut nbrs);
+            assert!(within.iter().all(|j| nbrs.contains(j)));
+        }
+    }
+}
+
+#[test]
+fn test_nbs2d_pairs_are_visited_once_against_brute_force() {
+    for &(no_x_cells, no_y_cells) in &[(5, 4), (1, 4), (1, 1), (2, 3)] {
+        let (x, y) = lattice_2d(no_x_cells, no_y_cells);
+        let mut nbs2d = NBS2D::from_limits_and_no_of_particles(
+            0.,
+            no_x_cells as f64,
+            0.,
+            no_y_cells as f64,
+            1.,
+            x.len(),
+        );
+        nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+        let mut pairs: Vec<(usize, usize)> = nbs2d.pairs().collect();
+        pairs.sort();
+        let mut expected_pairs = vec![];
+        for i in 0..x.len() {
+            for j in brute_force_2d(&x, &y, x[i], y[i]) {
+                if i < j {
+                    expected_pairs.push((i, j));
+                }
+            }
+        }
+        assert_eq!(expected_pairs, pairs);
+    }
+}
+
+#[test]
+fn test_nbs2d_pairs_within_a_cutoff_with_periodic_faces() {
+    let (x, y) = lattice_2d(3, 4);
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 3., 0., 4., 1., x.len());
+    nbs2d.set_periodic(true, true);
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+    // on a periodic lattice with a spacing of 0.5 every particle has 4
+    // partners at 0.5 and 4 at sqrt(0.5), shared between two particles
+    let pairs: Vec<(usize, usize)> = nbs2d.pairs_within(&x, &y, 0.5).collect();
+    assert_eq!(x.len() * 4 / 2, pairs.len());
+    let pairs: Vec<(usize, usize)> = nbs2d.pairs_within(&x, &y, 0.75).collect();
+    assert_eq!(x.len() * 8 / 2, pairs.len());
+
+    // every pair is also found by the radius query of each of its particles
+    for (i, j) in pairs {
+        assert!(i < j);
+        assert!(nbs2d.get_neighbours_within(i, &x, &y, &[0.], 0.75, true).contains(&j));
+        assert!(nbs2d.get_neighbours_within(j, &x, &y, &[0.], 0.75, true).contains(&i));
+    }
+}
+
+#[test]
+fn test_nbs2d_auto_fit_follows_drifting_particles() {
+    let mut nbs2d = NBS2D::new(0., 3., 0., 3., 1.);
+    nbs2d.set_auto_fit(Some(AutoFit::new(0.5, 0.5)));
+
+    // the particles fit in the initial domain, nothing is reallocated
+    let mut x = vec![0.5, 1.2, 2.5];
+    let mut y = vec![0.5, 1.1, 2.5];
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+    assert_eq!(9, nbs2d.head.len());
+    assert_eq!((0., 3.), (nbs2d.domain.min[0], nbs2d.domain.max[0]));
+
+    // drift out through the upper x face and the lower y face
+    for i in 0..x.len() {
+        x[i] += 2.;
+        y[i] -= 1.;
+    }
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+    assert_eq!(0., nbs2d.domain.min[0]);
+    assert!(nbs2d.domain.max[0] >= 4.5 + 0.5);
+    assert!(nbs2d.domain.min[1] <= -0.5 - 0.5);
+    assert!(nbs2d.domain.max[1] >= 3.);
+    assert_eq!(nbs2d.no_cells[0] * nbs2d.no_cells[1], nbs2d.head.len());
+
+    // no particle is lost
+    let mut all = vec![];
+    for i in 0..x.len() {
+        all.extend(nbs2d.get_neighbours(x[i], y[i], 0.));
+    }
+    all.sort_unstable();
+    all.dedup();
+    assert_eq!(vec![0, 1, 2], all);
+    assert_eq!(vec![1, 0], nbs2d.get_neighbours_within(1, &x, &y, &[0.], 1., false));
+
+    // a small drift stays within the room left by the growth
+    let no_cells = nbs2d.head.len();
+    for xi in x.iter_mut() {
+        *xi += 0.2;
+    }
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+    assert_eq!(no_cells, nbs2d.head.len());
+}
+
+#[test]
+fn test_nbs2d_auto_fit_keeps_periodic_axes() {
+    let mut nbs2d = NBS2D::new(0., 3., 0., 3., 1.);
+    nbs2d.set_periodic(true, false);
+    nbs2d.set_auto_fit(Some(AutoFit::new(0., 0.)));
+
+    // particles are wrapped along x and cover y
+    let x = vec![-0.5, 7.5];
+    let y = vec![1.5, 4.2];
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+    assert_eq!((0., 3.), (nbs2d.domain.min[0], nbs2d.domain.max[0]));
+    assert_eq!(5, nbs2d.no_cells[1]);
+    assert_eq!(vec![1], nbs2d.get_neighbours(1.5, 4.2, 0.));
+    assert_eq!(vec![0], nbs2d.get_neighbours(2.5, 1.5, 0.));
+}
+
+#[test]
+fn test_nbs2d_registration_reports_lost_particles() {
+    let x = vec![0.5, 3.5, f64::NAN, 1.5, -0.1, 2.5];
+    let y = vec![0.5, 0.5, 0.5, f64::INFINITY, 1.5, 2.5];
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 3., 0., 3., 1., x.len());
+
+    let report = nbs2d.register_particles_checked(&x, &y, &[0.]).unwrap();
+    assert_eq!(vec![1, 4], report.out_of_domain);
+    assert_eq!(vec![2, 3], report.non_finite);
+    assert_eq!(4, report.no_of_lost_particles());
+    // the other particles are registered
+    assert_eq!(vec![0], nbs2d.get_neighbours(0.5, 0.5, 0.));
+
+    // in strict mode the same particles give an error
+    nbs2d.set_strict(true);
+    let error = nbs2d.register_particles_checked(&x, &y, &[0.]).unwrap_err();
+    assert_eq!(RegistrationError::LostParticles { report }, error);
+
+    let report = nbs2d
+        .register_particles_checked(&[0.5, 1.5], &[0.5, 2.5], &[0.])
+        .unwrap();
+    assert!(report.is_clean());
+}
+
+#[test]
+fn test_nbs2d_registration_sizes_next() {
+    // none of these constructors sizes next for the particles
+    let x = vec![0.5, 1.5, 2.5];
+    let y = vec![0.5, 0.5, 2.5];
+    let grids = vec![
+        NBS2D::new(0., 3., 0., 3., 1.),
+        NBS2D::from_maximum_coordinate(3., 1.),
+    ];
+    for mut nbs2d in grids {
+        nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+        assert_eq!(x.len(), nbs2d.next.len());
+        assert_eq!(vec![1, 0], nbs2d.get_neighbours(1., 0.5, 0.));
+    }
+}
+
+#[test]
+#[should_panic(expected = "1 particles out of the domain")]
+fn test_nbs2d_strict_mode_panics_on_the_trait_registration() {
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 3., 0., 3., 1., 2);
+    nbs2d.set_strict(true);
+    nbs2d.register_particles_to_nnps(&[0.5, 3.5], &[0.5, 0.5], &[0.]);
+}
+
+#[test]
+fn test_nbs2d_try_new_rejects_invalid_parameters() {
+    assert_eq!(
+        Err(NnpsError::InvalidCellSize {
+            cell_size: 1.,
+            axis: 0
+        }),
+        NBS2D::try_new(0., 0.1, 0., 3., 1.).map(|_| ())
+    );
+    assert_eq!(
+        Err(NnpsError::InvalidCellSize {
+            cell_size: -0.5,
+            axis: 0
+        }),
+        NBS2D::try_new(0., 3., 0., 3., -0.5).map(|_| ())
+    );
+    assert_eq!(
+        Err(NnpsError::InvertedBounds {
+            axis: 1,
+            min: 3.,
+            max: 0.
+        }),
+        NBS2D::try_new(0., 3., 3., 0., 1.).map(|_| ())
+    );
+    assert_eq!(
+        Err(NnpsError::NonFinite),
+        NBS2D::try_new(0., f64::INFINITY, 0., 3., 1.).map(|_| ())
+    );
+    assert_eq!(
+        Err(NnpsError::CellCountOverflow),
+        NBS2D::try_new(0., 1e300, 0., 1e300, 1.).map(|_| ())
+    );
+
+    let nbs2d = NBS2D::try_new(0., 3., 0., 2., 1.).unwrap();
+    assert_eq!(6, nbs2d.head.len());
+}
+
+#[test]
+#[should_panic(expected = "lower limit")]
+fn test_nbs2d_new_panics_on_inverted_bounds() {
+    NBS2D::new(3., 0., 0., 3., 1.);
+}
+
+#[test]
+fn test_nbs2d_anisotropic_cells_in_a_channel() {
+    // a long and thin channel, with cells of 2 along x and 0.25 along y
+    let domain = Aabb::new([0., 0.], [10., 1.]);
+    let mut nbs2d = NBS2D::from_aabb_and_cell_sizes(domain, [2., 0.25]);
+    assert_eq!((5, 4), (nbs2d.no_cells[0], nbs2d.no_cells[1]));
+
+    let mut x = vec![];
+    let mut y = vec![];
+    for i in 0..40 {
+        for j in 0..8 {
+            x.push(0.125 + 0.25 * i as f64);
+            y.push(0.0625 + 0.125 * j as f64);
+        }
+    }
+    nbs2d.initialize_next(x.len());
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+
+    for i in (0..x.len()).step_by(7) {
+        // the candidates are the particles at most one cell away along
+        // each axis, with the cell size of that axis
+        let mut nbrs = nbs2d.get_neighbours(x[i], y[i], 0.);
+        nbrs.sort_unstable();
+        let expected: Vec<usize> = (0..x.len())
+            .filter(|&j| {
+                ((x[j] / 2.).floor() - (x[i] / 2.).floor()).abs() <= 1.
+                    && ((y[j] / 0.25).floor() - (y[i] / 0.25).floor()).abs() <= 1.
+            })
+            .collect();
+        assert_eq!(expected, nbrs);
+
+        let mut within = nbs2d.get_neighbours_within(i, &x, &y, &[0.], 0.25, false);
+        within.sort_unstable();
+        let expected: Vec<usize> = (0..x.len())
+            .filter(|&j| (x[i] - x[j]).powi(2) + (y[i] - y[j]).powi(2) <= 0.0625)
+            .collect();
+        assert_eq!(expected, within);
+    }
+}
+
+#[test]
+fn test_nbs2d_last_cell_of_a_row_does_not_spill_into_the_next_row() {
+    // 2.2 cells along x
+    let x = vec![2.1, 0.5, 0.5];
+    let y = vec![0.5, 0.5, 1.5];
+
+    // the last column stretches up to x = 2.2
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 2.2, 0., 3., 1., x.len());
+    assert_eq!(CellCountPolicy::ClampEdge, nbs2d.cell_count_policy);
+    assert_eq!(6, nbs2d.head.len());
+    let report = nbs2d.register_particles_checked(&x, &y, &[0.]).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(0, nbs2d.head[1]);
+    // the particle at x = 2.1 is not in the first cell of the second row
+    assert_eq!(vec![2], nbs2d.get_neighbours(0.5, 2.5, 0.));
+    let mut nbrs = nbs2d.get_neighbours(2.1, 0.5, 0.);
+    nbrs.sort_unstable();
+    assert_eq!(vec![0, 1, 2], nbrs);
+
+    // a third column reaching past the domain
+    nbs2d.set_cell_count_policy(CellCountPolicy::RoundUp);
+    assert_eq!((3, 3), (nbs2d.no_cells[0], nbs2d.no_cells[1]));
+    nbs2d.register_particles_to_nnps(&x, &y, &[0.]);
+    assert_eq!(0, nbs2d.head[2]);
+    assert_eq!(vec![2], nbs2d.get_neighbours(0.5, 2.5, 0.));
+    assert_eq!(vec![1, 2], nbs2d.get_neighbours(0.5, 0.5, 0.));
+}
+
+#[test]
+fn test_nbs2d_cell_count_policies_on_non_integer_domains() {
+    let x: Vec<f64> = (0..400).map(|i| (i as f64 * 0.618034) % 1. * 3.7).collect();
+    let y: Vec<f64> = (0..400).map(|i| (i as f64 * 0.414214) % 1. * 2.3).collect();
+
+    for &policy in &[CellCountPolicy::ClampEdge, CellCountPolicy::RoundUp] {
+        for &periodic in &[false, true] {
+            let mut nbs2d = NbsBuilder::new()
+                .x_limits(0., 3.7)
+                .y_limits(0., 2.3)
+                .cell_size(0.7)
+                .cell_count_policy(policy)
+                .periodic_x(periodic)
+                .no_of_particles(x.len())
+                .build_2d()
+                .unwrap();
+            let no_x_cells = match (policy, periodic) {
+                (CellCountPolicy::RoundUp, false) => 6,
+                _ => 5,
+            };
+            assert_eq!(no_x_cells, nbs2d.no_cells[0]);
+
+            let report = nbs2d.register_particles_checked(&x, &y, &[0.]).unwrap();
+            assert!(report.is_clean());
+            for i in 0..x.len() {
+                let mut found = nbs2d.get_neighbours_within(i, &x, &y, &[0.], 0.7, false);
+                found.sort_unstable();
+                let expected: Vec<usize> = (0..x.len())
+                    .filter(|&j| {
+                        let mut dx = (x[i] - x[j]).abs();
+                        if periodic {
+                            dx = dx.min(3.7 - dx);
+                        }
+                        dx * dx + (y[i] - y[j]).powi(2) <= 0.49
+                    })
+                    .collect();
+                assert_eq!(expected, found);
+            }
+        }
+    }
+}
@@ -1,8 +1,14 @@
+// lints raised by the original tests
+#![allow(clippy::legacy_numeric_constants, unused_variables)]
 extern crate neighbours;
 
 // local library imports
+use neighbours::aabb::Aabb;
+use neighbours::auto_fit::AutoFit;
+use neighbours::cell_count::CellCountPolicy;
 use neighbours::nbs3d::NBS3D;
-use neighbours::NNPS;
+use neighbours::registration::RegistrationError;
+use neighbours::{NnpsError, NNPS};
 
 #[test]
 fn test_nbs3d_creation_for_a_given_domain_limits_case_1() {
@@ -249,3 +255,384 @@ fn test_nbs3d_10_particles_on_x_axis() {
     // ];
     // assert_eq!(expected_neighbours, nbrs);
 }
+
+#[test]
+fn test_nbs3d_periodic_neighbours_across_the_z_face() {
+    let x = vec![1.5, 1.5, 1.5];
+    let y = vec![1.5, 1.5, 1.5];
+    let z = vec![0.1, 2.9, 1.5];
+
+    let mut nbs3d = NBS3D::new(0., 3., 0., 3., 0., 3., 1.);
+    nbs3d.initialize_next(x.len());
+    nbs3d.set_periodic(false, false, true);
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    // the particle close to the top face is seen one domain length below
+    let nbrs = nbs3d.get_neighbours_with_shifts(1.5, 1.5, 0.1);
+    assert_eq!(vec![(0, [0., 0., 0.]), (2, [0., 0., 0.]), (1, [0., 0., -3.])], nbrs);
+
+    let nbrs = nbs3d.get_neighbours(1.5, 1.5, 2.9);
+    assert_eq!(vec![1, 0, 2], nbrs);
+}
+
+#[test]
+fn test_nbs3d_periodic_shift_in_all_directions() {
+    // a single particle in the corner cell
+    let x = vec![2.5];
+    let y = vec![2.5];
+    let z = vec![2.5];
+
+    let mut nbs3d = NBS3D::new(0., 3., 0., 3., 0., 3., 1.);
+    nbs3d.initialize_next(x.len());
+    nbs3d.set_periodic(true, true, true);
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    // query from the opposite corner
+    let nbrs = nbs3d.get_neighbours_with_shifts(0.5, 0.5, 0.5);
+    assert_eq!(vec![(0, [-3., -3., -3.])], nbrs);
+
+    // a query point outside the box is wrapped as well
+    let nbrs = nbs3d.get_neighbours_with_shifts(3.5, 3.5, 3.5);
+    assert_eq!(vec![(0, [-3., -3., -3.])], nbrs);
+}
+
+/// Particles on a lattice with one particle at a quarter and one at three
+/// quarters of every cell, along every direction.
+fn lattice_3d(no_cells: [usize; 3]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let mut x = vec![];
+    let mut y = vec![];
+    let mut z = vec![];
+    for k in 0..2 * no_cells[2] {
+        for j in 0..2 * no_cells[1] {
+            for i in 0..2 * no_cells[0] {
+                x.push(0.25 + 0.5 * i as f64);
+                y.push(0.25 + 0.5 * j as f64);
+                z.push(0.25 + 0.5 * k as f64);
+            }
+        }
+    }
+    (x, y, z)
+}
+
+/// Brute force neighbours, all the particles whose cell is at most one cell
+/// away from the cell of the query point along each axis.
+fn brute_force_3d(x: &[f64], y: &[f64], z: &[f64], query: [f64; 3]) -> Vec<usize> {
+    let adjacent = |a: f64, b: f64| (a.floor() as isize - b.floor() as isize).abs() <= 1;
+    let mut neighbours: Vec<usize> = (0..x.len())
+        .filter(|&i| {
+            adjacent(x[i], query[0]) && adjacent(y[i], query[1]) && adjacent(z[i], query[2])
+        })
+        .collect();
+    neighbours.sort();
+    neighbours
+}
+
+#[test]
+fn test_nbs3d_get_neighbours_on_boundary_cells_against_brute_force() {
+    for &no_cells in &[[4, 3, 3], [1, 3, 2], [3, 1, 1], [1, 1, 1], [2, 2, 4]] {
+        let (x, y, z) = lattice_3d(no_cells);
+        let mut nbs3d = NBS3D::new(
+            0.,
+            no_cells[0] as f64,
+            0.,
+            no_cells[1] as f64,
+            0.,
+            no_cells[2] as f64,
+            1.,
+        );
+        nbs3d.initialize_next(x.len());
+        nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+        // query from the particle positions, so that every corner, edge and
+        // face cell is visited
+        for i in 0..x.len() {
+            let mut nbrs = nbs3d.get_neighbours(x[i], y[i], z[i]);
+            nbrs.sort();
+            assert_eq!(brute_force_3d(&x, &y, &z, [x[i], y[i], z[i]]), nbrs);
+        }
+    }
+}
+
+#[test]
+fn test_nbs3d_get_neighbours_within_a_radius() {
+    let x = vec![1.5, 1.5, 1.5, 2.2];
+    let y = vec![1.5, 1.5, 1.5, 2.2];
+    let z = vec![1.5, 2.4, 0.1, 2.2];
+
+    let mut nbs3d = NBS3D::new(0., 3., 0., 3., 0., 3., 1.);
+    nbs3d.initialize_next(x.len());
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    // all the particles are in the stencil, but only particle 1 is close
+    // enough, particle 3 is sqrt(0.49 + 0.49 + 0.49) away
+    let nbrs = nbs3d.get_neighbours(1.5, 1.5, 1.5);
+    assert_eq!(4, nbrs.len());
+    let nbrs = nbs3d.get_neighbours_within(0, &x, &y, &z, 1., true);
+    assert_eq!(vec![1], nbrs);
+
+    let mut nbrs = nbs3d.get_neighbours_within(0, &x, &y, &z, 1.25, false);
+    nbrs.sort();
+    assert_eq!(vec![0, 1, 3], nbrs);
+}
+
+#[test]
+fn test_nbs3d_get_neighbours_within_a_radius_across_a_periodic_face() {
+    let x = vec![1.5, 1.5];
+    let y = vec![1.5, 1.5];
+    let z = vec![0.1, 2.8];
+
+    let mut nbs3d = NBS3D::new(0., 3., 0., 3., 0., 3., 1.);
+    nbs3d.initialize_next(x.len());
+    nbs3d.set_periodic(false, false, true);
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    let nbrs = nbs3d.get_neighbours_within(0, &x, &y, &z, 0.35, true);
+    assert_eq!(vec![1], nbrs);
+    let nbrs = nbs3d.get_neighbours_within(0, &x, &y, &z, 0.25, true);
+    let expected_neighbours: Vec<usize> = vec![];
+    assert_eq!(expected_neighbours, nbrs);
+}
+
+#[test]
+fn test_nbs3d_neighbours_iter_and_get_neighbours_into() {
+    let (x, y, z) = lattice_3d([3, 3, 3]);
+    let mut nbs3d = NBS3D::from_maximum_and_no_of_particles(1.5, 1., x.len());
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    // the lattice starts at the origin, query it shifted into [-1.5, 1.5]
+    let mut nbrs = vec![];
+    for i in 0..x.len() {
+        let (xi, yi, zi) = (x[i] - 1.5, y[i] - 1.5, z[i] - 1.5);
+        let expected_neighbours = nbs3d.get_neighbours(xi, yi, zi);
+        nbs3d.get_neighbours_into(xi, yi, zi, &mut nbrs);
+        assert_eq!(expected_neighbours, nbrs);
+
+        let mut iter = nbs3d.neighbours_iter(xi, yi, zi);
+        assert_eq!(Some(expected_neighbours[0]), iter.next());
+        assert_eq!(expected_neighbours.len() - 1, iter.count());
+    }
+}
+
+#[test]
+fn test_nbs3d_pairs_are_visited_once_against_brute_force() {
+    for &no_cells in &[[4, 3, 3], [1, 3, 2], [1, 1, 1], [2, 2, 4]] {
+        let (x, y, z) = lattice_3d(no_cells);
+        let mut nbs3d = NBS3D::new(
+            0.,
+            no_cells[0] as f64,
+            0.,
+            no_cells[1] as f64,
+            0.,
+            no_cells[2] as f64,
+            1.,
+        );
+        nbs3d.initialize_next(x.len());
+        nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+        let mut pairs: Vec<(usize, usize)> = nbs3d.pairs().collect();
+        pairs.sort();
+        let mut expected_pairs = vec![];
+        for i in 0..x.len() {
+            for j in brute_force_3d(&x, &y, &z, [x[i], y[i], z[i]]) {
+                if i < j {
+                    expected_pairs.push((i, j));
+                }
+            }
+        }
+        assert_eq!(expected_pairs, pairs);
+    }
+}
+
+#[test]
+fn test_nbs3d_pairs_within_a_cutoff_with_periodic_faces() {
+    let (x, y, z) = lattice_3d([3, 4, 3]);
+    let mut nbs3d = NBS3D::new(0., 3., 0., 4., 0., 3., 1.);
+    nbs3d.initialize_next(x.len());
+    nbs3d.set_periodic(true, true, true);
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    // on a periodic lattice with a spacing of 0.5 every particle has 6
+    // partners at 0.5, shared between two particles
+    let pairs: Vec<(usize, usize, [f64; 3])> = nbs3d.pairs_with_shifts().collect();
+    let mut close_pairs = 0;
+    for &(i, j, shift) in &pairs {
+        assert!(i < j);
+        let dx = x[i] - x[j] - shift[0];
+        let dy = y[i] - y[j] - shift[1];
+        let dz = z[i] - z[j] - shift[2];
+        if dx * dx + dy * dy + dz * dz <= 0.25 + 1e-12 {
+            close_pairs += 1;
+        }
+    }
+    assert_eq!(x.len() * 6 / 2, close_pairs);
+    assert_eq!(close_pairs, nbs3d.pairs_within(&x, &y, &z, 0.5 + 1e-9).count());
+}
+
+#[test]
+fn test_nbs3d_auto_fit_covers_every_particle() {
+    let (mut x, mut y, mut z) = lattice_3d([3, 3, 3]);
+    // scatter some particles far out of the initial domain
+    x[0] = -7.3;
+    y[5] = 12.1;
+    z[9] = 4.;
+    x[10] = f64::NAN;
+
+    let mut nbs3d = NBS3D::new(0., 3., 0., 3., 0., 3., 1.);
+    nbs3d.set_auto_fit(Some(AutoFit::new(0.1, 0.)));
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    assert_eq!(x.len(), nbs3d.next.len());
+    assert_eq!(
+        nbs3d.no_cells[0] * nbs3d.no_cells[1] * nbs3d.no_cells[2],
+        nbs3d.head.len()
+    );
+    for i in (0..x.len()).filter(|&i| i != 10) {
+        let mut found = nbs3d.get_neighbours_within(i, &x, &y, &z, 1., false);
+        found.sort_unstable();
+        let expected: Vec<usize> = brute_force_3d(&x, &y, &z, [x[i], y[i], z[i]])
+            .into_iter()
+            .filter(|&j| {
+                let dx = x[i] - x[j];
+                let dy = y[i] - y[j];
+                let dz = z[i] - z[j];
+                dx * dx + dy * dy + dz * dz <= 1.
+            })
+            .collect();
+        assert_eq!(expected, found);
+    }
+}
+
+#[test]
+fn test_nbs3d_auto_fit_rejects_a_blown_up_particle() {
+    let (x, y, z) = lattice_3d([3, 3, 3]);
+    let mut nbs3d = NBS3D::new(0., 3., 0., 3., 0., 3., 0.01);
+    nbs3d.set_auto_fit(Some(AutoFit::new(0., 0.)));
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+    let (domain, no_cells) = (nbs3d.domain, nbs3d.no_cells);
+
+    // far too many cells to allocate, or even to count
+    for &blown_up in &[1e12, 1e300] {
+        let mut x = x.clone();
+        x[4] = blown_up;
+        assert_eq!(
+            Err(RegistrationError::Nnps(NnpsError::CellCountOverflow)),
+            nbs3d.register_particles_checked(&x, &y, &z)
+        );
+        // the grid is left as it was
+        assert_eq!(domain, nbs3d.domain);
+        assert_eq!(no_cells, nbs3d.no_cells);
+        assert_eq!(300 * 300 * 300, nbs3d.head.len());
+    }
+}
+
+#[test]
+fn test_nbs3d_strict_registration_catches_blow_ups() {
+    let (mut x, y, mut z) = lattice_3d([3, 3, 3]);
+    let mut nbs3d = NBS3D::new(0., 3., 0., 3., 0., 3., 1.);
+    nbs3d.initialize_next(x.len());
+    nbs3d.set_strict(true);
+    assert!(nbs3d.register_particles_checked(&x, &y, &z).is_ok());
+
+    z[7] = f64::NAN;
+    x[12] = 1e9;
+    let error = nbs3d.register_particles_checked(&x, &y, &z).unwrap_err();
+    match &error {
+        RegistrationError::LostParticles { report } => {
+            assert_eq!(vec![12], report.out_of_domain);
+            assert_eq!(vec![7], report.non_finite);
+        }
+        error => panic!("unexpected error {:?}", error),
+    }
+    assert_eq!(
+        "1 particles out of the domain and 1 particles with a non finite position",
+        error.to_string()
+    );
+}
+
+#[test]
+fn test_nbs3d_try_new_rejects_invalid_parameters() {
+    assert_eq!(
+        Err(NnpsError::InvalidCellSize {
+            cell_size: 0.,
+            axis: 0
+        }),
+        NBS3D::try_new(0., 3., 0., 3., 0., 3., 0.).map(|_| ())
+    );
+    assert_eq!(
+        Err(NnpsError::InvertedBounds {
+            axis: 2,
+            min: 1.,
+            max: 1.
+        }),
+        NBS3D::try_new(0., 3., 0., 3., 1., 1., 0.5).map(|_| ())
+    );
+    assert_eq!(
+        Err(NnpsError::NonFinite),
+        NBS3D::try_new(0., 3., f64::NAN, 3., 0., 3., 1.).map(|_| ())
+    );
+    assert_eq!(
+        Err(NnpsError::CellCountOverflow),
+        NBS3D::try_new(0., 1e7, 0., 1e7, 0., 1e7, 1e-3).map(|_| ())
+    );
+    assert_eq!(27, NBS3D::try_new(0., 3., 0., 3., 0., 3., 1.).unwrap().total_no_cells);
+}
+
+#[test]
+fn test_nbs3d_anisotropic_cells_per_axis() {
+    let domain = Aabb::new([0., 0., 0.], [4., 2., 0.5]);
+    let nbs3d = NBS3D::from_aabb_and_cell_sizes(domain, [1., 0.5, 0.1]);
+    assert_eq!(
+        [4, 4, 5],
+        [nbs3d.no_cells[0], nbs3d.no_cells[1], nbs3d.no_cells[2]]
+    );
+    assert_eq!(80, nbs3d.head.len());
+
+    // every axis is checked against its own cell size
+    assert_eq!(
+        Err(NnpsError::InvalidCellSize {
+            cell_size: 1.,
+            axis: 2
+        }),
+        NBS3D::try_from_aabb_and_cell_sizes(domain, [1., 1., 1.]).map(|_| ())
+    );
+
+    let mut nbs3d = nbs3d;
+    let x = vec![0.5, 0.5, 0.5, 1.5];
+    let y = vec![0.25, 0.25, 0.25, 0.25];
+    let z = vec![0.05, 0.15, 0.35, 0.05];
+    nbs3d.initialize_next(x.len());
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+    let mut nbrs = nbs3d.get_neighbours(0.5, 0.25, 0.05);
+    nbrs.sort_unstable();
+    assert_eq!(vec![0, 1, 3], nbrs);
+}
+
+#[test]
+fn test_nbs3d_particles_on_the_upper_faces_are_registered() {
+    // 2.5 cells along every axis, particles in the leftover part and on the
+    // upper faces of the domain
+    let x = vec![2.4, 2.5, 0.5, 2.5];
+    let y = vec![0.5, 2.5, 2.45, 1.];
+    let z = vec![0.5, 2.5, 0.5, 2.2];
+    for &policy in &[CellCountPolicy::ClampEdge, CellCountPolicy::RoundUp] {
+        let mut nbs3d = NBS3D::from_limits_and_no_of_particles(0., 2.5, 0., 2.5, 0., 2.5, 1., 4);
+        nbs3d.set_cell_count_policy(policy);
+        let no_cells = if policy == CellCountPolicy::RoundUp { 3 } else { 2 };
+        assert_eq!(no_cells * no_cells * no_cells, nbs3d.total_no_cells);
+
+        let report = nbs3d.register_particles_checked(&x, &y, &z).unwrap();
+        assert!(report.is_clean());
+        for i in 0..x.len() {
+            assert!(nbs3d.get_neighbours(x[i], y[i], z[i]).contains(&i));
+        }
+        // only the particles in the cells around the origin
+        let mut nbrs = nbs3d.get_neighbours(0.5, 0.5, 0.5);
+        nbrs.sort_unstable();
+        let expected = if policy == CellCountPolicy::RoundUp {
+            vec![]
+        } else {
+            vec![0, 1, 2, 3]
+        };
+        assert_eq!(expected, nbrs);
+    }
+}
@@ -0,0 +1,100 @@
+extern crate neighbours;
+
+mod common;
+use common::{brute_force, scattered};
+
+// local library imports
+use neighbours::octree_nnps::{OctNode, OctTree};
+use neighbours::NNPS;
+
+/// Scattered but reproducible points in `[0, 1)`, squeezed towards the
+/// origin so that the distribution is strongly clustered.
+fn clustered(no_of_particles: usize, seed: u64) -> Vec<f64> {
+    scattered(no_of_particles, seed)
+        .into_iter()
+        .map(|u| u * u * u)
+        .collect()
+}
+
+fn max_leaf_size(node: &OctNode) -> usize {
+    if node.children.is_empty() {
+        node.particles.len()
+    } else {
+        node.children.iter().map(max_leaf_size).max().unwrap()
+    }
+}
+
+#[test]
+fn test_octree_get_neighbours_against_brute_force() {
+    let x = clustered(2000, 1);
+    let y = clustered(2000, 2);
+    let z = clustered(2000, 3);
+
+    let mut tree = OctTree::new(3, 0.05, 8);
+    tree.register_particles_to_nnps(&x, &y, &z);
+
+    // the clustered particles need a deep tree, but no leaf is over capacity
+    assert!(tree.depth() > 4);
+    assert!(max_leaf_size(tree.root.as_ref().unwrap()) <= 8);
+
+    for i in (0..x.len()).step_by(7) {
+        let mut nbrs = tree.get_neighbours(x[i], y[i], z[i]);
+        nbrs.sort();
+        assert_eq!(brute_force([&x, &y, &z], [x[i], y[i], z[i]], 0.05), nbrs);
+    }
+
+    // a larger radius than the one the tree was built with
+    let mut nbrs = tree.get_neighbours_in_radius(0.5, 0.5, 0.5, 0.6);
+    nbrs.sort();
+    assert_eq!(brute_force([&x, &y, &z], [0.5, 0.5, 0.5], 0.6), nbrs);
+}
+
+#[test]
+fn test_quadtree_get_neighbours_within_against_brute_force() {
+    let x = clustered(1000, 4);
+    let y = clustered(1000, 5);
+    let z = vec![0.; x.len()];
+
+    let mut tree = OctTree::new(2, 0.02, 4);
+    // the z coordinate is ignored in 2D
+    tree.register_particles_to_nnps(&x, &y, &[0.]);
+    assert_eq!(4, tree.root.as_ref().unwrap().children.len());
+
+    for i in (0..x.len()).step_by(3) {
+        let mut nbrs = tree.get_neighbours_within(i, &x, &y, &[0.], 0.03, true);
+        nbrs.sort();
+        let expected_neighbours: Vec<usize> = brute_force([&x, &y, &z], [x[i], y[i], 0.], 0.03)
+            .into_iter()
+            .filter(|&j| j != i)
+            .collect();
+        assert_eq!(expected_neighbours, nbrs);
+    }
+}
+
+#[test]
+fn test_octree_with_coincident_and_non_finite_particles() {
+    // more coincident particles than the leaf capacity
+    let mut x = vec![0.5; 10];
+    let mut y = vec![0.5; 10];
+    let mut z = vec![0.5; 10];
+    x.push(f64::NAN);
+    y.push(0.5);
+    z.push(0.5);
+
+    let mut tree = OctTree::new(3, 0.1, 2);
+    tree.register_particles_to_nnps(&x, &y, &z);
+
+    let mut nbrs = tree.get_neighbours(0.5, 0.5, 0.5);
+    nbrs.sort();
+    assert_eq!((0..10).collect::<Vec<usize>>(), nbrs);
+
+    // registering again replaces the previous particles
+    tree.register_particles_to_nnps(&[], &[], &[]);
+    assert!(tree.get_neighbours(0.5, 0.5, 0.5).is_empty());
+}
+
+#[test]
+#[should_panic]
+fn test_octree_in_1d() {
+    OctTree::new(1, 0.1, 2);
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "rayon")]
+extern crate neighbours;
+
+mod common;
+use common::scattered_in;
+
+// local library imports
+use neighbours::nbs2d::NBS2D;
+use neighbours::nbs3d::NBS3D;
+use neighbours::parallel::par_neighbour_lists;
+use neighbours::NNPS;
+
+#[test]
+fn test_nbs2d_par_register_matches_the_serial_registration() {
+    let x = scattered_in(2000, 10., 1);
+    let mut y = scattered_in(2000, 10., 2);
+    // a few particles out of the domain
+    y[10] = -1.;
+    y[500] = 11.;
+
+    let mut serial = NBS2D::from_limits_and_no_of_particles(0., 10., 0., 10., 0.5, x.len());
+    serial.register_particles_to_nnps(&x, &y, &[0.]);
+    let mut parallel = NBS2D::from_limits_and_no_of_particles(0., 10., 0., 10., 0.5, x.len());
+    parallel.par_register_particles_to_nnps(&x, &y, &[0.]);
+
+    assert_eq!(serial.head, parallel.head);
+    assert_eq!(serial.next, parallel.next);
+}
+
+#[test]
+fn test_nbs3d_par_register_matches_the_serial_registration() {
+    let x = scattered_in(3000, 4., 3);
+    let y = scattered_in(3000, 4., 4);
+    let z = scattered_in(3000, 4., 5);
+
+    let mut serial = NBS3D::new(0., 4., 0., 4., 0., 4., 0.5);
+    serial.initialize_next(x.len());
+    serial.set_periodic(true, false, true);
+    serial.register_particles_to_nnps(&x, &y, &z);
+    let mut parallel = serial.clone();
+    parallel.par_register_particles_to_nnps(&x, &y, &z);
+
+    assert_eq!(serial.head, parallel.head);
+    assert_eq!(serial.next, parallel.next);
+}
+
+#[test]
+fn test_nbs2d_par_register_sizes_next() {
+    let x = scattered_in(100, 10., 3);
+    let y = scattered_in(100, 10., 4);
+    let mut nbs2d = NBS2D::new(0., 10., 0., 10., 1.);
+    nbs2d.par_register_particles_to_nnps(&x, &y, &[]);
+    assert_eq!(x.len(), nbs2d.next.len());
+
+    let mut serial = NBS2D::from_limits_and_no_of_particles(0., 10., 0., 10., 1., x.len());
+    serial.register_particles_to_nnps(&x, &y, &[]);
+    assert_eq!(serial.head, nbs2d.head);
+    assert_eq!(serial.next, nbs2d.next);
+}
+
+#[test]
+#[should_panic(expected = "2 particles out of the domain")]
+fn test_nbs2d_par_register_panics_in_strict_mode() {
+    let x = scattered_in(100, 10., 1);
+    let mut y = scattered_in(100, 10., 2);
+    y[10] = -1.;
+    y[50] = 11.;
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 10., 0., 10., 1., x.len());
+    nbs2d.set_strict(true);
+    nbs2d.par_register_particles_to_nnps(&x, &y, &[]);
+}
+
+#[test]
+fn test_par_neighbour_lists_matches_the_serial_queries() {
+    let x = scattered_in(1000, 5., 6);
+    let y = scattered_in(1000, 5., 7);
+    let z = scattered_in(1000, 5., 8);
+
+    let mut nbs3d = NBS3D::new(0., 5., 0., 5., 0., 5., 0.5);
+    nbs3d.initialize_next(x.len());
+    nbs3d.par_register_particles_to_nnps(&x, &y, &z);
+
+    let lists = par_neighbour_lists(&nbs3d, &x, &y, &z, 0.5, true);
+    assert_eq!(x.len(), lists.len());
+    for (i, list) in lists.iter().enumerate() {
+        assert_eq!(&nbs3d.get_neighbours_within(i, &x, &y, &z, 0.5, true), list);
+    }
+
+    // the 2d grid ignores z
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 5., 0., 5., 0.5, x.len());
+    nbs2d.par_register_particles_to_nnps(&x, &y, &[0.]);
+    let lists = par_neighbour_lists(&nbs2d, &x, &y, &[0.], 0.5, false);
+    for (i, list) in lists.iter().enumerate() {
+        assert_eq!(
+            &nbs2d.get_neighbours_within(i, &x, &y, &[0.], 0.5, false),
+            list
+        );
+    }
+}
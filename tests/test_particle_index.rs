@@ -0,0 +1,150 @@
+extern crate neighbours;
+
+// local library imports
+use neighbours::builder::NbsBuilder;
+use neighbours::cell_grid::CellGrid;
+use neighbours::nbs3d::NBS3D;
+use neighbours::particle_index::ParticleIndex;
+use neighbours::registration::RegistrationError;
+use neighbours::{NnpsError, NNPS};
+
+/// Particles on a regular lattice in `[0, 3)^3`, two per cell and per axis.
+fn lattice_3d() -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let (mut x, mut y, mut z) = (vec![], vec![], vec![]);
+    for k in 0..6 {
+        for j in 0..6 {
+            for i in 0..6 {
+                x.push(0.25 + 0.5 * i as f64);
+                y.push(0.25 + 0.5 * j as f64);
+                z.push(0.25 + 0.5 * k as f64);
+            }
+        }
+    }
+    (x, y, z)
+}
+
+#[test]
+fn test_particle_index_sentinels() {
+    assert_eq!(usize::MAX, usize::NONE);
+    assert_eq!(u32::MAX, u32::NONE);
+    assert_eq!(u32::MAX as usize, u32::MAX_PARTICLES);
+    assert_eq!(7, u32::from_usize(7).to_usize());
+}
+
+#[test]
+fn test_particle_index_compact_grid_matches_the_usize_grid() {
+    let (x, y, z) = lattice_3d();
+
+    let mut nbs3d = NBS3D::from_limits_and_no_of_particles(0., 3., 0., 3., 0., 3., 1., x.len());
+    nbs3d.set_periodic(true, false, true);
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+
+    let mut compact = CellGrid::<3, f64, u32>::from_limits_and_no_of_particles(
+        0.,
+        3.,
+        0.,
+        3.,
+        0.,
+        3.,
+        1.,
+        x.len(),
+    );
+    compact.set_periodic(true, false, true);
+    compact.register_particles_to_nnps(&x, &y, &z);
+
+    // same lists, with the empty marker and the indices stored on 4 bytes
+    assert_eq!(
+        std::mem::size_of::<u32>(),
+        std::mem::size_of_val(&compact.head[0])
+    );
+    let widen = |v: &[u32]| -> Vec<usize> {
+        v.iter()
+            .map(|&i| {
+                if i == u32::NONE {
+                    usize::NONE
+                } else {
+                    i as usize
+                }
+            })
+            .collect()
+    };
+    assert_eq!(nbs3d.head, widen(&compact.head));
+    assert_eq!(nbs3d.next, widen(&compact.next));
+
+    for i in 0..x.len() {
+        assert_eq!(
+            nbs3d.get_neighbours(x[i], y[i], z[i]),
+            compact.get_neighbours(x[i], y[i], z[i])
+        );
+    }
+    assert_eq!(
+        nbs3d.pairs_with_shifts().collect::<Vec<_>>(),
+        compact.pairs_with_shifts().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_particle_index_overflow_is_reported() {
+    let too_many = u32::MAX as usize + 1;
+
+    let result = NbsBuilder::new()
+        .max_coordinate(1.)
+        .cell_size(0.5)
+        .capacity(too_many)
+        .build::<2, f64, u32>();
+    assert_eq!(
+        Err(NnpsError::TooManyParticles {
+            no_of_particles: too_many,
+            max: u32::MAX as usize
+        }),
+        result.map(|grid| grid.total_no_cells)
+    );
+}
+
+#[test]
+#[should_panic(expected = "particles do not fit")]
+fn test_particle_index_overflow_panics_when_sizing_next() {
+    let mut compact = CellGrid::<2, f64, u32>::new(0., 1., 0., 1., 0.5);
+    compact.initialize_next(u32::MAX as usize + 1);
+}
+
+/// Index type addressing at most 3 particles, to reach the limit of the
+/// index type with a handful of particles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TinyIndex(u8);
+
+impl ParticleIndex for TinyIndex {
+    const NONE: TinyIndex = TinyIndex(u8::MAX);
+    const MAX_PARTICLES: usize = 3;
+
+    fn from_usize(i: usize) -> TinyIndex {
+        TinyIndex(i as u8)
+    }
+
+    fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[test]
+fn test_particle_index_overflow_is_reported_by_the_checked_registration() {
+    let mut grid = CellGrid::<1, f64, TinyIndex>::new(0., 4., 1.);
+    let report = grid
+        .register_particles_checked(&[0.5, 1.5, 2.5], &[], &[])
+        .unwrap();
+    assert!(report.is_clean());
+
+    let error = grid
+        .register_particles_checked(&[0.5, 1.5, 2.5, 3.5], &[], &[])
+        .unwrap_err();
+    assert_eq!(
+        RegistrationError::Nnps(NnpsError::TooManyParticles {
+            no_of_particles: 4,
+            max: 3
+        }),
+        error
+    );
+    // the grid still holds the previous registration
+    assert_eq!(3, grid.next.len());
+    assert_eq!(vec![2, 1], grid.get_neighbours(2., 0., 0.));
+}
@@ -0,0 +1,107 @@
+extern crate neighbours;
+
+mod common;
+use common::random_states;
+
+// local library imports
+use neighbours::builder::NbsBuilder;
+use neighbours::cell_grid::CellGrid;
+use neighbours::hash_grid::HashGrid;
+use neighbours::kdtree::KdTree;
+use neighbours::octree_nnps::OctTree;
+use neighbours::real::Real;
+use neighbours::NNPS;
+
+/// Scattered but reproducible points in `[0, 1)`, in `f32` so that the
+/// `f64` copies hold exactly the same positions.
+fn scattered_f32(no_of_particles: usize, seed: u64) -> Vec<f32> {
+    random_states(no_of_particles, seed)
+        .map(|state| (state >> 40) as f32 / (1u64 << 24) as f32)
+        .collect()
+}
+
+fn to_f64(values: &[f32]) -> Vec<f64> {
+    values.iter().map(|&v| v as f64).collect()
+}
+
+/// Neighbour lists of every particle within `h`, sorted.
+fn all_neighbours<T: Real, N: NNPS<T>>(
+    nnps: &N,
+    x: &[T],
+    y: &[T],
+    z: &[T],
+    h: T,
+) -> Vec<Vec<usize>> {
+    (0..x.len())
+        .map(|i| {
+            let mut nbrs = nnps.get_neighbours_within(i, x, y, z, h, true);
+            nbrs.sort();
+            nbrs
+        })
+        .collect()
+}
+
+#[test]
+fn test_real_cell_grids_accept_f32_coordinates() {
+    let x = scattered_f32(400, 1);
+    let y = scattered_f32(400, 2);
+    let z = scattered_f32(400, 3);
+    let (x64, y64, z64) = (to_f64(&x), to_f64(&y), to_f64(&z));
+    let h = 0.1;
+
+    let mut nbs3d_f32 =
+        CellGrid::<3, f32>::from_limits_and_no_of_particles(0., 1., 0., 1., 0., 1., h, x.len());
+    nbs3d_f32.register_particles_to_nnps(&x, &y, &z);
+    let mut nbs3d =
+        CellGrid::<3>::from_limits_and_no_of_particles(0., 1., 0., 1., 0., 1., h, x.len());
+    nbs3d.register_particles_to_nnps(&x64, &y64, &z64);
+
+    assert_eq!(nbs3d.head, nbs3d_f32.head);
+    assert_eq!(nbs3d.next, nbs3d_f32.next);
+    assert_eq!(
+        all_neighbours(&nbs3d, &x64, &y64, &z64, h),
+        all_neighbours(&nbs3d_f32, &x, &y, &z, h as f32)
+    );
+
+    // the 2D grid from the builder, with distances in f32
+    let mut nbs2d: CellGrid<2, f32> = NbsBuilder::new()
+        .max_coordinate(1.)
+        .cell_size(h)
+        .periodic_x(true)
+        .no_of_particles(x.len())
+        .build()
+        .unwrap();
+    nbs2d.register_particles_to_nnps(&x, &y, &[]);
+    let csr = nbs2d.get_neighbours_csr(&x, &y, &[], h as f32, true, true);
+    let distances: &[f32] = csr.distances.as_ref().unwrap();
+    assert!(distances.iter().all(|&r| r <= h as f32));
+    let pairs: Vec<(usize, usize)> = nbs2d.pairs_within(&x, &y, h as f32).collect();
+    assert_eq!(csr.indices.len(), 2 * pairs.len());
+}
+
+#[test]
+fn test_real_other_backends_accept_f32_coordinates() {
+    let x = scattered_f32(400, 4);
+    let y = scattered_f32(400, 5);
+    let z = scattered_f32(400, 6);
+    let (x64, y64, z64) = (to_f64(&x), to_f64(&y), to_f64(&z));
+    let h = 0.1;
+
+    let mut hash_grid = HashGrid::<f64>::new(3, h);
+    hash_grid.register_particles_to_nnps(&x64, &y64, &z64);
+    let expected = all_neighbours(&hash_grid, &x64, &y64, &z64, h);
+
+    let mut hash_grid = HashGrid::<f32>::new(3, h);
+    hash_grid.register_particles_to_nnps(&x, &y, &z);
+    assert_eq!(expected, all_neighbours(&hash_grid, &x, &y, &z, h as f32));
+
+    let mut octree = OctTree::new(3, h as f32, 8);
+    octree.register_particles_to_nnps(&x, &y, &z);
+    assert_eq!(expected, all_neighbours(&octree, &x, &y, &z, h as f32));
+
+    let mut kdtree = KdTree::new(3, h as f32);
+    kdtree.register_particles_to_nnps(&x, &y, &z);
+    assert_eq!(expected, all_neighbours(&kdtree, &x, &y, &z, h as f32));
+    let nearest: Vec<(usize, f32)> = kdtree.get_k_nearest_with_distances(x[0], y[0], z[0], 1);
+    assert_eq!(vec![(0, 0.)], nearest);
+}
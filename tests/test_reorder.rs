@@ -0,0 +1,124 @@
+extern crate neighbours;
+
+mod common;
+use common::scattered;
+
+// local library imports
+use neighbours::morton;
+use neighbours::nbs2d::NBS2D;
+use neighbours::nbs3d::NBS3D;
+use neighbours::particle_array::ParticleArray;
+use neighbours::reorder::SortKey;
+use neighbours::NNPS;
+
+#[test]
+fn test_reorder_morton_keys() {
+    assert_eq!(0, morton::encode_2d(0, 0));
+    assert_eq!(0b1, morton::encode_2d(1, 0));
+    assert_eq!(0b10, morton::encode_2d(0, 1));
+    assert_eq!(0b1110, morton::encode_2d(2, 3));
+    assert_eq!(0b111, morton::encode_3d(1, 1, 1));
+    assert_eq!(0b10_100, morton::encode_3d(0, 2, 1));
+    assert_eq!(morton::encode_3d(5, 6, 7), morton::encode([5, 6, 7]));
+    assert_eq!(9, morton::encode([9]));
+}
+
+#[test]
+fn test_reorder_sorting_permutation_follows_the_cells() {
+    // one particle per cell of a 4 x 4 grid, registered in reverse order
+    let mut x = vec![];
+    let mut y = vec![];
+    for j in (0..4).rev() {
+        for i in (0..4).rev() {
+            x.push(i as f64 + 0.5);
+            y.push(j as f64 + 0.5);
+        }
+    }
+    // and one particle out of the domain
+    x.push(10.);
+    y.push(0.5);
+    let nbs2d = NBS2D::new(0., 4., 0., 4., 1.);
+
+    let order = nbs2d.sorting_permutation(&x, &y, &[], SortKey::CellIndex);
+    let expected: Vec<usize> = (0..16).rev().chain(Some(16)).collect();
+    assert_eq!(expected, order);
+
+    // the Z curve visits the 2 x 2 blocks one after the other
+    let order = nbs2d.sorting_permutation(&x, &y, &[], SortKey::Morton);
+    let cells: Vec<(f64, f64)> = order[..8].iter().map(|&i| (x[i], y[i])).collect();
+    assert_eq!(
+        vec![
+            (0.5, 0.5),
+            (1.5, 0.5),
+            (0.5, 1.5),
+            (1.5, 1.5),
+            (2.5, 0.5),
+            (3.5, 0.5),
+            (2.5, 1.5),
+            (3.5, 1.5)
+        ],
+        cells
+    );
+    assert_eq!(16, order[16]);
+}
+
+#[test]
+fn test_reorder_particle_array_keeps_the_neighbours() {
+    let x = scattered(500, 1);
+    let y = scattered(500, 2);
+    let z = scattered(500, 3);
+    let radius: Vec<f64> = (0..x.len()).map(|i| i as f64).collect();
+    let mut particles = ParticleArray::from_xyz_rad(&x, &y, &z, &radius);
+    let h = 0.1;
+
+    let mut nbs3d = NBS3D::from_limits_and_no_of_particles(0., 1., 0., 1., 0., 1., h, x.len());
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+    for &key in &[SortKey::CellIndex, SortKey::Morton] {
+        let (x_before, radius_before) = (particles.x.clone(), particles.radius.clone());
+        let inverse = nbs3d.reorder(&mut particles, key);
+        // the inverse mapping sends every particle to its new position
+        for (old, &new) in inverse.iter().enumerate() {
+            assert_eq!(x_before[old], particles.x[new]);
+            assert_eq!(radius_before[old], particles.radius[new]);
+        }
+        // the radius holds the index of the particle before the first sort
+        for i in 0..x.len() {
+            let k = particles.radius[i] as usize;
+            assert_eq!(
+                (x[k], y[k], z[k]),
+                (particles.x[i], particles.y[i], particles.z[i])
+            );
+        }
+    }
+
+    // the particles of every cell now sit next to each other, so the lists
+    // come out as contiguous runs
+    nbs3d.reorder(&mut particles, SortKey::CellIndex);
+    nbs3d.register_particles_to_nnps(&particles.x, &particles.y, &particles.z);
+    for (i, &next) in nbs3d.next.iter().enumerate() {
+        assert!(next == usize::MAX || next + 1 == i);
+    }
+
+    // and the neighbours are the same particles as before the sort
+    let mut original = NBS3D::from_limits_and_no_of_particles(0., 1., 0., 1., 0., 1., h, x.len());
+    original.register_particles_to_nnps(&x, &y, &z);
+    for i in 0..x.len() {
+        let k = particles.radius[i] as usize;
+        let mut nbrs: Vec<usize> = nbs3d
+            .get_neighbours_within(i, &particles.x, &particles.y, &particles.z, h, false)
+            .into_iter()
+            .map(|j| particles.radius[j] as usize)
+            .collect();
+        let mut expected = original.get_neighbours_within(k, &x, &y, &z, h, false);
+        nbrs.sort();
+        expected.sort();
+        assert_eq!(expected, nbrs);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_reorder_rejects_a_non_permutation() {
+    let mut particles = ParticleArray::new(3);
+    particles.permute(&[0, 0, 1]);
+}
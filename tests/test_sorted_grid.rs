@@ -0,0 +1,96 @@
+extern crate neighbours;
+
+mod common;
+use common::scattered;
+
+// local library imports
+use neighbours::cell_grid::CellGrid;
+use neighbours::nbs2d::NBS2D;
+use neighbours::nbs3d::NBS3D;
+use neighbours::sorted_grid::SortedCellGrid;
+use neighbours::NNPS;
+
+#[test]
+fn test_sorted_grid_counting_sort() {
+    // two particles in the first cell, one in the last, one out of domain
+    let x = vec![0.2, 1.5, 0.7, 5.];
+    let y = vec![0.3, 1.5, 0.1, 0.5];
+    let mut sorted = SortedCellGrid::new(NBS2D::new(0., 2., 0., 2., 1.));
+    sorted.register_particles_to_nnps(&x, &y, &[]);
+
+    assert_eq!(vec![2, 0, 0, 1], sorted.cell_count);
+    assert_eq!(vec![0, 2, 2, 2], sorted.cell_start);
+    assert_eq!(vec![0, 2, 1], sorted.particles);
+    assert_eq!(&[0, 2], sorted.cell_particles(0));
+    assert!(sorted.cell_particles(1).is_empty());
+    assert_eq!(&[1], sorted.cell_particles(3));
+}
+
+#[test]
+fn test_sorted_grid_matches_the_linked_lists() {
+    let x = scattered(400, 1);
+    let y = scattered(400, 2);
+    let z = scattered(400, 3);
+    let h = 0.15;
+
+    let mut nbs3d = NBS3D::from_limits_and_no_of_particles(0., 1., 0., 1., 0., 1., h, x.len());
+    nbs3d.set_periodic(true, false, true);
+    let mut sorted = SortedCellGrid::new(nbs3d.clone());
+    nbs3d.register_particles_to_nnps(&x, &y, &z);
+    sorted.register_particles_to_nnps(&x, &y, &z);
+
+    // every cell holds the same particles, in increasing index order
+    for idx in 0..nbs3d.total_no_cells {
+        let mut expected = vec![];
+        let mut j = nbs3d.head[idx];
+        while j != usize::MAX {
+            expected.push(j);
+            j = nbs3d.next[j];
+        }
+        expected.reverse();
+        assert_eq!(&expected[..], sorted.cell_particles(idx));
+    }
+
+    for i in 0..x.len() {
+        let mut expected = nbs3d.get_neighbours_with_shifts(x[i], y[i], z[i]);
+        let mut nbrs = sorted.get_neighbours_with_shifts(x[i], y[i], z[i]);
+        expected.sort_by_key(|&(j, _)| j);
+        nbrs.sort_by_key(|&(j, _)| j);
+        assert_eq!(expected, nbrs);
+
+        let mut expected = nbs3d.get_neighbours_within(i, &x, &y, &z, h, true);
+        let mut nbrs = sorted.get_neighbours_within(i, &x, &y, &z, h, true);
+        expected.sort();
+        nbrs.sort();
+        assert_eq!(expected, nbrs);
+    }
+}
+
+#[test]
+fn test_sorted_grid_with_compact_indices() {
+    let x = scattered(200, 4);
+    let y = scattered(200, 5);
+
+    let mut nbs2d = NBS2D::from_limits_and_no_of_particles(0., 1., 0., 1., 0.1, x.len());
+    nbs2d.register_particles_to_nnps(&x, &y, &[]);
+    let mut sorted = SortedCellGrid::new(CellGrid::<2, f64, u32>::new(0., 1., 0., 1., 0.1));
+    sorted.register_particles_to_nnps(&x, &y, &[]);
+
+    assert_eq!(x.len(), sorted.particles.len());
+    for i in 0..x.len() {
+        let mut expected = nbs2d.get_neighbours(x[i], y[i], 0.);
+        let mut nbrs = sorted.get_neighbours(x[i], y[i], 0.);
+        expected.sort();
+        nbrs.sort();
+        assert_eq!(expected, nbrs);
+    }
+}
+
+#[test]
+#[should_panic(expected = "1 particles with a non finite position")]
+fn test_sorted_grid_strict_mode_panics_on_lost_particles() {
+    let mut nbs2d = NBS2D::new(0., 2., 0., 2., 1.);
+    nbs2d.set_strict(true);
+    let mut sorted = SortedCellGrid::new(nbs2d);
+    sorted.register_particles_to_nnps(&[0.2, f64::NAN], &[0.3, 0.5], &[]);
+}
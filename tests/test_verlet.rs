@@ -0,0 +1,126 @@
+extern crate neighbours;
+
+mod common;
+use common::{brute_force, scattered};
+
+// local library imports
+use neighbours::nbs2d::NBS2D;
+use neighbours::nbs3d::NBS3D;
+use neighbours::verlet::VerletList;
+use neighbours::NNPS;
+
+#[test]
+fn test_verlet_lists_stay_exact_until_the_rebuild() {
+    let mut x = scattered(300, 1);
+    let mut y = scattered(300, 2);
+    let (h, skin) = (0.08, 0.02);
+
+    // the cells cover the search range plus the skin
+    let nbs2d = NBS2D::from_limits_and_no_of_particles(-1., 2., -1., 2., h + skin, x.len());
+    let mut verlet = VerletList::new(nbs2d, 2, h, skin);
+    assert!(verlet.update(&x, &y, &[]));
+    assert_eq!((1, 1), (verlet.no_of_updates, verlet.no_of_builds));
+
+    // every step moves the particles by 0.004, so the lists last until the
+    // displacement goes past 0.01, at the third step
+    let velocity: Vec<(f64, f64)> = (0..x.len())
+        .map(|i| {
+            let angle = 2. * std::f64::consts::PI * i as f64 / x.len() as f64;
+            (0.004 * angle.cos(), 0.004 * angle.sin())
+        })
+        .collect();
+    let mut rebuilt = vec![];
+    for _ in 0..6 {
+        for i in 0..x.len() {
+            x[i] += velocity[i].0;
+            y[i] += velocity[i].1;
+        }
+        rebuilt.push(verlet.update(&x, &y, &[]));
+
+        // the neighbours within h are exact whether the lists were rebuilt
+        // or not
+        for i in 0..x.len() {
+            let mut nbrs = verlet.get_neighbours(i, &x, &y, &[]);
+            nbrs.sort();
+            let expected: Vec<usize> = brute_force([&x, &y], [x[i], y[i]], h)
+                .into_iter()
+                .filter(|&j| j != i)
+                .collect();
+            assert_eq!(expected, nbrs);
+        }
+    }
+    assert_eq!(vec![false, false, true, false, false, true], rebuilt);
+    assert_eq!((7, 3), (verlet.no_of_updates, verlet.no_of_builds));
+    assert!((verlet.rebuild_ratio() - 3. / 7.).abs() < 1e-12);
+}
+
+#[test]
+fn test_verlet_lists_rebuild_when_the_particles_change() {
+    let x = scattered(100, 3);
+    let y = scattered(100, 4);
+    let z = scattered(100, 5);
+
+    let nbs3d = NBS3D::from_limits_and_no_of_particles(0., 1., 0., 1., 0., 1., 0.25, x.len());
+    let mut verlet = VerletList::new(nbs3d, 3, 0.2, 0.05);
+    assert!(verlet.update(&x, &y, &z));
+    assert!(!verlet.update(&x, &y, &z));
+    assert_eq!(0., verlet.max_displacement);
+
+    // the candidates are the neighbours of the cell list within h + skin
+    for i in 0..x.len() {
+        let mut candidates = verlet.candidates(i).to_vec();
+        let mut expected = verlet.nnps.get_neighbours_within(i, &x, &y, &z, 0.25, true);
+        candidates.sort();
+        expected.sort();
+        assert_eq!(expected, candidates);
+    }
+
+    // a single particle moving farther than skin / 2 triggers a rebuild
+    let mut moved = x.clone();
+    moved[10] += 0.03;
+    assert!(verlet.update(&moved, &y, &z));
+
+    // and so does a new number of particles
+    let mut nbs3d = NBS3D::from_limits_and_no_of_particles(0., 1., 0., 1., 0., 1., 0.25, 50);
+    nbs3d.register_particles_to_nnps(&x[..50], &y[..50], &z[..50]);
+    let mut verlet = VerletList::new(nbs3d, 3, 0.2, 0.05);
+    verlet.update(&x[..50], &y[..50], &z[..50]);
+    verlet.nnps.initialize_next(40);
+    assert!(verlet.update(&x[..40], &y[..40], &z[..40]));
+    assert_eq!(2, verlet.no_of_builds);
+}
+
+#[test]
+#[should_panic]
+fn test_verlet_lists_reject_a_negative_skin() {
+    let nbs2d = NBS2D::new(0., 1., 0., 1., 0.5);
+    VerletList::new(nbs2d, 2, 0.4, -0.1);
+}
+
+#[test]
+fn test_verlet_lists_across_periodic_faces() {
+    let mut x = vec![0.1, 4.9, 2.5];
+    let y = vec![0.5, 0.5, 0.5];
+    let (h, skin) = (0.8, 0.4);
+    let mut nbs2d = NBS2D::new(0., 5., 0., 5., h + skin);
+    nbs2d.set_periodic(true, false);
+    let mut verlet = VerletList::new(nbs2d, 2, h, skin);
+    assert_eq!([Some(5.), None, None], verlet.nnps.periods());
+    verlet.update(&x, &y, &[]);
+
+    // the neighbours through the x face are at their minimum image distance
+    assert_eq!(&[1], verlet.candidates(0));
+    let mut nbrs = vec![];
+    verlet.for_each_neighbour(0, &x, &y, &[], |j, r| nbrs.push((j, r)));
+    assert_eq!(1, nbrs.len());
+    assert_eq!(1, nbrs[0].0);
+    assert!((nbrs[0].1 - 0.2).abs() < 1e-12);
+    assert_eq!(vec![0], verlet.get_neighbours(1, &x, &y, &[]));
+
+    // a particle wrapped back into the domain has only moved by 0.12, so
+    // the lists are kept
+    x[1] = 0.02;
+    assert!(!verlet.update(&x, &y, &[]));
+    assert_eq!(vec![1], verlet.get_neighbours(0, &x, &y, &[]));
+    assert!(verlet.get_neighbours(2, &x, &y, &[]).is_empty());
+}